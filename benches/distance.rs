@@ -0,0 +1,95 @@
+use bitpart::metric::Metric;
+use bitpart_fast_euclidean::FastEuclidean;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+
+const DIMENSIONS: [usize; 4] = [16, 128, 960, 4096];
+
+/// Deterministic pseudo-random coordinates in `[0, 1)`.
+fn point(dimension: usize, seed: u64) -> Vec<f64> {
+    let mut state = seed;
+    (0..dimension)
+        .map(|_| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (state >> 11) as f64 / (1u64 << 53) as f64
+        })
+        .collect()
+}
+
+/// The generic iterator fold `FastEuclidean` used before the slice kernels.
+fn iterator_fold<'a>(
+    a: impl IntoIterator<Item = &'a f64>,
+    b: impl IntoIterator<Item = &'a f64>,
+) -> f64 {
+    a.into_iter()
+        .zip(b)
+        .map(|(&x, &y)| (x - y) * (x - y))
+        .fold(0.0, |acc, v| acc + v)
+        .sqrt()
+}
+
+fn distance(c: &mut Criterion) {
+    let mut group = c.benchmark_group("distance");
+    for dimension in DIMENSIONS {
+        let a = FastEuclidean::try_new(point(dimension, 1)).unwrap();
+        let b = FastEuclidean::try_new(point(dimension, 2)).unwrap();
+
+        group.bench_with_input(
+            BenchmarkId::new("iterator_fold", dimension),
+            &(&a, &b),
+            |bencher, (a, b)| bencher.iter(|| iterator_fold(black_box(&***a), black_box(&***b))),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("kernel", dimension),
+            &(&a, &b),
+            |bencher, (a, b)| bencher.iter(|| black_box(*a).distance(black_box(*b))),
+        );
+    }
+    group.finish();
+}
+
+/// Scans candidates for those within a radius of the query, where almost every candidate lies
+/// well outside the radius.
+fn bounded(c: &mut Criterion) {
+    const CANDIDATES: u64 = 256;
+
+    let mut group = c.benchmark_group("bounded");
+    for dimension in [960, 4096] {
+        let query = FastEuclidean::try_new(point(dimension, 0)).unwrap();
+        let candidates: Vec<_> = (1..=CANDIDATES)
+            .map(|seed| FastEuclidean::try_new(point(dimension, seed)).unwrap())
+            .collect();
+        // Uniform points in the unit cube are about `sqrt(dimension / 6)` apart.
+        let radius = (dimension as f64 / 6.0).sqrt() / 4.0;
+
+        group.bench_with_input(
+            BenchmarkId::new("distance", dimension),
+            &candidates,
+            |bencher, candidates| {
+                bencher.iter(|| {
+                    candidates
+                        .iter()
+                        .filter(|c| query.distance(black_box(c)) <= radius)
+                        .count()
+                })
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("distance_bounded", dimension),
+            &candidates,
+            |bencher, candidates| {
+                bencher.iter(|| {
+                    candidates
+                        .iter()
+                        .filter_map(|c| query.distance_bounded(black_box(c), radius))
+                        .count()
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, distance, bounded);
+criterion_main!(benches);
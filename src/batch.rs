@@ -0,0 +1,151 @@
+//! One-to-many distance calculations.
+
+use std::iter::FusedIterator;
+
+use crate::element::private::Sealed;
+use crate::kernel::MANY;
+use crate::{Coordinates, FastEuclidean};
+
+impl<T> FastEuclidean<T>
+where
+    T: Coordinates,
+{
+    /// Calculates the distance from this point to each of `others`, writing them to `out` in the
+    /// same order.
+    ///
+    /// Targets are processed several at a time, reading the coordinates of this point once per
+    /// group. This is the building block for comparing a point against every reference point.
+    /// # Panics
+    /// Panics if `others` and `out` have different lengths.
+    /// # Example
+    /// ```
+    /// # use bitpart_fast_euclidean::FastEuclidean;
+    /// let query = FastEuclidean::<[f64; 2]>::try_new([0.0, 0.0]).unwrap();
+    /// let pivots: Vec<_> = (0..5)
+    ///     .map(|i| FastEuclidean::<[f64; 2]>::try_new([i as f64, 0.0]).unwrap())
+    ///     .collect();
+    ///
+    /// let mut out = [0.0; 5];
+    /// query.distances_to_many(&pivots, &mut out);
+    /// assert_eq!(out, [0.0, 1.0, 2.0, 3.0, 4.0]);
+    /// ```
+    pub fn distances_to_many(&self, others: &[Self], out: &mut [f64]) {
+        assert_eq!(
+            others.len(),
+            out.len(),
+            "output length does not match number of points"
+        );
+        let (groups, others) = others.as_chunks::<MANY>();
+        let (out_groups, out) = out.as_chunks_mut::<MANY>();
+        for (group, out) in groups.iter().zip(out_groups) {
+            *out = self.distances_to_group(group.each_ref());
+        }
+        for (other, out) in others.iter().zip(out) {
+            *out = self.squared_distance(other).sqrt();
+        }
+    }
+
+    /// Returns an iterator over the distances from this point to each of `others`.
+    ///
+    /// Like [`distances_to_many`](Self::distances_to_many), targets are processed several at a
+    /// time, so the iterator reads ahead of the distance it yields.
+    /// # Example
+    /// ```
+    /// # use bitpart_fast_euclidean::FastEuclidean;
+    /// let query = FastEuclidean::<[f64; 2]>::try_new([0.0, 0.0]).unwrap();
+    /// let pivots: Vec<_> = (0..5)
+    ///     .map(|i| FastEuclidean::<[f64; 2]>::try_new([0.0, i as f64]).unwrap())
+    ///     .collect();
+    ///
+    /// let nearest = query
+    ///     .distances_to_many_iter(&pivots)
+    ///     .filter(|&d| d < 2.5)
+    ///     .count();
+    /// assert_eq!(nearest, 3);
+    /// ```
+    pub fn distances_to_many_iter<'a, I>(&'a self, others: I) -> DistancesToMany<'a, T, I::IntoIter>
+    where
+        I: IntoIterator<Item = &'a Self>,
+    {
+        DistancesToMany {
+            query: self,
+            others: others.into_iter(),
+            buffer: [0.0; MANY],
+            next: 0,
+            len: 0,
+        }
+    }
+
+    fn distances_to_group(&self, group: [&Self; MANY]) -> [f64; MANY] {
+        let query = self.0.coordinates();
+        let targets = group.map(|other| other.0.coordinates());
+        debug_assert!(
+            targets.iter().all(|t| t.len() == query.len()),
+            "points have different dimensions"
+        );
+        T::Element::squared_euclidean_many(query, targets).map(f64::sqrt)
+    }
+}
+
+/// Iterator over the distances from a point to a sequence of others.
+///
+/// Created by [`FastEuclidean::distances_to_many_iter`].
+#[derive(Debug, Clone)]
+pub struct DistancesToMany<'a, T, I> {
+    query: &'a FastEuclidean<T>,
+    others: I,
+    buffer: [f64; MANY],
+    next: usize,
+    len: usize,
+}
+
+impl<'a, T, I> Iterator for DistancesToMany<'a, T, I>
+where
+    T: Coordinates + 'a,
+    I: Iterator<Item = &'a FastEuclidean<T>>,
+{
+    type Item = f64;
+
+    fn next(&mut self) -> Option<f64> {
+        if self.next == self.len {
+            let mut group = [None; MANY];
+            self.len = 0;
+            for (slot, other) in group.iter_mut().zip(&mut self.others) {
+                *slot = Some(other);
+                self.len += 1;
+            }
+            self.next = 0;
+            match group {
+                [Some(a), Some(b), Some(c), Some(d)] => {
+                    self.buffer = self.query.distances_to_group([a, b, c, d]);
+                }
+                _ => {
+                    for (out, other) in self.buffer.iter_mut().zip(group.into_iter().flatten()) {
+                        *out = self.query.squared_distance(other).sqrt();
+                    }
+                }
+            }
+        }
+        if self.next == self.len {
+            return None;
+        }
+        self.next += 1;
+        Some(self.buffer[self.next - 1])
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let buffered = self.len - self.next;
+        let (lower, upper) = self.others.size_hint();
+        (
+            lower.saturating_add(buffered),
+            upper.and_then(|upper| upper.checked_add(buffered)),
+        )
+    }
+}
+
+impl<'a, T, I> FusedIterator for DistancesToMany<'a, T, I>
+where
+    T: Coordinates + 'a,
+    I: FusedIterator<Item = &'a FastEuclidean<T>>,
+{
+}
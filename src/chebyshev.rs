@@ -0,0 +1,87 @@
+use bitpart::metric::Metric;
+
+use crate::element::private::Sealed;
+use crate::kernel::Chebyshev;
+use crate::{validate_within, Coordinates, ValidationError, BOUNDED_BLOCK};
+
+/// Wrapper struct to apply Chebyshev (L∞) distance to an object set.
+///
+/// The distance is the largest difference in any one coordinate, computed with a vectorised
+/// max-reduction under the same constructor contract as [`FastEuclidean`](crate::FastEuclidean).
+/// # Example
+/// ```
+/// # use bitpart::metric::Metric;
+/// # use bitpart_fast_euclidean::FastChebyshev;
+/// let point1 = FastChebyshev::<[f64; 3]>::try_new([0.0, 0.0, 0.0]).unwrap();
+/// let point2 = FastChebyshev::<[f64; 3]>::try_new([1.0, -4.0, 2.0]).unwrap();
+///
+/// assert_eq!(point1.distance(&point2), 4.0);
+/// ```
+#[derive(Debug, Clone)]
+pub struct FastChebyshev<T>(T);
+
+impl<T> FastChebyshev<T> {
+    /// Creates a new `FastChebyshev`.
+    /// # Safety
+    /// You **must** ensure that the Chebyshev distance measurements between
+    /// any two points cannot return [`NaN`](f64::NAN) nor [`INF`](f64::INFINITY).
+    /// For `f32` coordinates, the distance must also fit in an `f32`.
+    pub unsafe fn new(t: T) -> Self {
+        Self(t)
+    }
+}
+
+impl<T> FastChebyshev<T>
+where
+    T: Coordinates,
+{
+    /// Creates a new `FastChebyshev`, checking that the safety requirements of [`new`](Self::new)
+    /// are upheld.
+    ///
+    /// Every coordinate must be finite, and small enough in magnitude that its difference from a
+    /// coordinate of any other point passing this check cannot overflow.
+    pub fn try_new(t: T) -> Result<Self, ValidationError> {
+        validate_within(t.coordinates(), T::Element::ACCUMULATOR_MAX / 4.0)?;
+        Ok(Self(t))
+    }
+
+    /// Calculates the distance to `rhs` if it does not exceed `limit`.
+    ///
+    /// Any single coordinate differing by more than `limit` decides the result, so the
+    /// calculation is abandoned at the end of the first block of coordinates containing one.
+    /// # Example
+    /// ```
+    /// # use bitpart_fast_euclidean::FastChebyshev;
+    /// let point1 = FastChebyshev::<[f64; 2]>::try_new([0.0, 0.0]).unwrap();
+    /// let point2 = FastChebyshev::<[f64; 2]>::try_new([3.0, 4.0]).unwrap();
+    ///
+    /// assert_eq!(point1.distance_bounded(&point2, 4.0), Some(4.0));
+    /// assert_eq!(point1.distance_bounded(&point2, 3.5), None);
+    /// ```
+    pub fn distance_bounded(&self, rhs: &Self, limit: f64) -> Option<f64> {
+        let (lhs, rhs) = (self.0.coordinates(), rhs.0.coordinates());
+        debug_assert_eq!(lhs.len(), rhs.len(), "points have different dimensions");
+        let mut max = 0.0;
+        for (x, y) in lhs.chunks(BOUNDED_BLOCK).zip(rhs.chunks(BOUNDED_BLOCK)) {
+            max = f64::max(max, T::Element::reduce::<Chebyshev>(x, y));
+            if max > limit {
+                return None;
+            }
+        }
+        (max <= limit).then_some(max)
+    }
+}
+
+impl_wrapper!(FastChebyshev);
+
+/// Bounds as for [`FastEuclidean`](crate::FastEuclidean).
+impl<T> Metric for FastChebyshev<T>
+where
+    T: Coordinates + Clone,
+{
+    fn distance(&self, rhs: &FastChebyshev<T>) -> f64 {
+        let (lhs, rhs) = (self.0.coordinates(), rhs.0.coordinates());
+        debug_assert_eq!(lhs.len(), rhs.len(), "points have different dimensions");
+        T::Element::reduce::<Chebyshev>(lhs, rhs)
+    }
+}
@@ -0,0 +1,106 @@
+use std::borrow::Cow;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use crate::Element;
+
+/// Point types whose coordinates are stored contiguously.
+///
+/// The fast metrics operate on slices so that their kernels can process several coordinates at a
+/// time. This is implemented for arrays, slices, [`Vec`] and the common smart pointers; other
+/// containers only need to expose their coordinates as a slice.
+pub trait Coordinates {
+    /// Type of each coordinate.
+    type Element: Element;
+
+    /// Returns the coordinates of the point.
+    fn coordinates(&self) -> &[Self::Element];
+}
+
+impl<E> Coordinates for [E]
+where
+    E: Element,
+{
+    type Element = E;
+
+    fn coordinates(&self) -> &[E] {
+        self
+    }
+}
+
+impl<E, const N: usize> Coordinates for [E; N]
+where
+    E: Element,
+{
+    type Element = E;
+
+    fn coordinates(&self) -> &[E] {
+        self
+    }
+}
+
+impl<E> Coordinates for Vec<E>
+where
+    E: Element,
+{
+    type Element = E;
+
+    fn coordinates(&self) -> &[E] {
+        self
+    }
+}
+
+impl<E> Coordinates for Cow<'_, [E]>
+where
+    E: Element,
+{
+    type Element = E;
+
+    fn coordinates(&self) -> &[E] {
+        self
+    }
+}
+
+impl<C> Coordinates for &C
+where
+    C: Coordinates + ?Sized,
+{
+    type Element = C::Element;
+
+    fn coordinates(&self) -> &[Self::Element] {
+        (**self).coordinates()
+    }
+}
+
+impl<C> Coordinates for Box<C>
+where
+    C: Coordinates + ?Sized,
+{
+    type Element = C::Element;
+
+    fn coordinates(&self) -> &[Self::Element] {
+        (**self).coordinates()
+    }
+}
+
+impl<C> Coordinates for Rc<C>
+where
+    C: Coordinates + ?Sized,
+{
+    type Element = C::Element;
+
+    fn coordinates(&self) -> &[Self::Element] {
+        (**self).coordinates()
+    }
+}
+
+impl<C> Coordinates for Arc<C>
+where
+    C: Coordinates + ?Sized,
+{
+    type Element = C::Element;
+
+    fn coordinates(&self) -> &[Self::Element] {
+        (**self).coordinates()
+    }
+}
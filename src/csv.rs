@@ -0,0 +1,329 @@
+//! Streaming reader for points stored as delimited text, such as CSV or TSV.
+//!
+//! Each non-blank line is a point, with one coordinate per field. Fields are trimmed of
+//! surrounding whitespace, but quoting is not supported. Optionally, the first line is a header,
+//! and one column holds an identifier rather than a coordinate.
+//!
+//! Errors report the 1-based line number they occur on.
+
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::marker::PhantomData;
+use std::path::Path;
+
+use crate::{validate, DatasetError, Element, FastEuclidean, FileError};
+
+/// Layout of a delimited text file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Options {
+    /// Character separating fields.
+    pub delimiter: char,
+    /// Whether the first line is a header, to be skipped.
+    pub header: bool,
+    /// Index of the field holding each point's identifier, if any.
+    pub id_column: Option<usize>,
+}
+
+impl Options {
+    /// Comma-separated fields, without a header or identifiers.
+    pub fn csv() -> Self {
+        Self {
+            delimiter: ',',
+            header: false,
+            id_column: None,
+        }
+    }
+
+    /// Tab-separated fields, without a header or identifiers.
+    pub fn tsv() -> Self {
+        Self {
+            delimiter: '\t',
+            ..Self::csv()
+        }
+    }
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self::csv()
+    }
+}
+
+/// A point read from a delimited text file.
+#[derive(Debug, Clone)]
+pub struct Record<E = f64> {
+    /// The contents of the identifier column, if one was configured.
+    pub id: Option<String>,
+    /// The coordinates in the remaining columns.
+    pub point: FastEuclidean<Vec<E>>,
+}
+
+/// Streaming reader yielding the points of a delimited text file.
+///
+/// Every point is validated as in [`FastEuclidean::try_new`], so `NaN` and infinite values are
+/// rejected, and must have the same dimension as the first. Iteration stops after the first
+/// error.
+/// # Example
+/// ```
+/// # use bitpart_fast_euclidean::csv::{Options, Reader};
+/// let file = "id\tx\ty\na\t0.0\t1.0\nb\t2.5\t-1.0\n";
+/// let options = Options {
+///     header: true,
+///     id_column: Some(0),
+///     ..Options::tsv()
+/// };
+///
+/// let reader = Reader::<_, f64>::new(file.as_bytes(), options).unwrap();
+/// assert_eq!(reader.header(), Some(&["id", "x", "y"].map(String::from)[..]));
+///
+/// let records = reader.collect::<Result<Vec<_>, _>>().unwrap();
+/// assert_eq!(records[1].id.as_deref(), Some("b"));
+/// assert_eq!(records[1].point[..], [2.5, -1.0]);
+/// ```
+#[derive(Debug)]
+pub struct Reader<R, E = f64> {
+    reader: R,
+    options: Options,
+    header: Option<Vec<String>>,
+    line: usize,
+    text: String,
+    dimension: Option<usize>,
+    point: usize,
+    failed: bool,
+    _element: PhantomData<E>,
+}
+
+impl<E> Reader<BufReader<File>, E>
+where
+    E: Element,
+{
+    /// Opens the file at `path`.
+    pub fn open<P: AsRef<Path>>(path: P, options: Options) -> Result<Self, FileError> {
+        Self::new(BufReader::new(File::open(path)?), options)
+    }
+}
+
+impl<R, E> Reader<R, E>
+where
+    R: BufRead,
+    E: Element,
+{
+    /// Creates a reader of points laid out as in `options`, reading the header if there is one.
+    pub fn new(reader: R, options: Options) -> Result<Self, FileError> {
+        let mut reader = Self {
+            reader,
+            options,
+            header: None,
+            line: 0,
+            text: String::new(),
+            dimension: None,
+            point: 0,
+            failed: false,
+            _element: PhantomData,
+        };
+        if reader.options.header && reader.read_line()? {
+            let delimiter = reader.options.delimiter;
+            reader.header = Some(
+                reader
+                    .text
+                    .split(delimiter)
+                    .map(|field| field.trim().to_owned())
+                    .collect(),
+            );
+        }
+        Ok(reader)
+    }
+
+    /// Returns the fields of the header line, if the file has one.
+    pub fn header(&self) -> Option<&[String]> {
+        self.header.as_deref()
+    }
+
+    /// Returns the dimension of the points read so far, or `None` if no point has been read.
+    pub fn dimension(&self) -> Option<usize> {
+        self.dimension
+    }
+
+    /// Reads the next line into `text`, without its line ending. Returns `false` at the end of the
+    /// input.
+    fn read_line(&mut self) -> Result<bool, FileError> {
+        self.text.clear();
+        if self.reader.read_line(&mut self.text)? == 0 {
+            return Ok(false);
+        }
+        self.line += 1;
+        let len = self.text.trim_end_matches(['\n', '\r']).len();
+        self.text.truncate(len);
+        Ok(true)
+    }
+
+    fn read_record(&mut self) -> Result<Option<Record<E>>, FileError> {
+        while self.read_line()? {
+            if self.text.trim().is_empty() {
+                continue;
+            }
+
+            let mut id = None;
+            let mut coordinates = Vec::with_capacity(self.dimension.unwrap_or(0));
+            for (column, field) in self.text.split(self.options.delimiter).enumerate() {
+                let field = field.trim();
+                if Some(column) == self.options.id_column {
+                    id = Some(field.to_owned());
+                    continue;
+                }
+                let value: f64 = field.parse().map_err(|_| FileError::Parse {
+                    line: self.line,
+                    column: column + 1,
+                    text: field.to_owned(),
+                })?;
+                coordinates.push(E::from_f64(value));
+            }
+            if self.options.id_column.is_some() && id.is_none() {
+                return Err(FileError::Format(format!(
+                    "line {} has no identifier column",
+                    self.line
+                )));
+            }
+
+            let line = self.line;
+            let expected = *self.dimension.get_or_insert(coordinates.len());
+            if coordinates.len() != expected {
+                return Err(FileError::Line {
+                    line,
+                    source: DatasetError::Dimension {
+                        point: self.point,
+                        expected,
+                        found: coordinates.len(),
+                    },
+                });
+            }
+            validate(&coordinates).map_err(|source| FileError::Line {
+                line,
+                source: DatasetError::Invalid {
+                    point: self.point,
+                    source,
+                },
+            })?;
+            self.point += 1;
+            return Ok(Some(Record {
+                id,
+                point: FastEuclidean(coordinates),
+            }));
+        }
+        Ok(None)
+    }
+}
+
+impl<R, E> Iterator for Reader<R, E>
+where
+    R: BufRead,
+    E: Element,
+{
+    type Item = Result<Record<E>, FileError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let result = self.read_record().transpose();
+        self.failed = matches!(result, Some(Err(_)));
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ValidationError;
+
+    fn read_all(file: &str, options: Options) -> Result<Vec<Record>, FileError> {
+        Reader::new(file.as_bytes(), options)?.collect()
+    }
+
+    fn with_header() -> Options {
+        Options {
+            header: true,
+            ..Options::csv()
+        }
+    }
+
+    #[test]
+    fn skips_blank_lines() {
+        let records = read_all("x,y\n\n0, 1\r\n  \n2,3\n", with_header()).unwrap();
+        let points: Vec<_> = records.iter().map(|r| r.point.0.clone()).collect();
+        assert_eq!(points, [[0.0, 1.0], [2.0, 3.0]]);
+        assert!(records.iter().all(|r| r.id.is_none()));
+        assert!(read_all("", with_header()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn reports_parse_errors() {
+        match read_all("x,y\n\n0,1\n2,two\n", with_header()) {
+            Err(FileError::Parse { line, column, text }) => {
+                assert_eq!((line, column, text.as_str()), (4, 2, "two"))
+            }
+            other => panic!("unexpected result {other:?}"),
+        }
+        // Columns count the identifier too.
+        let options = Options {
+            id_column: Some(0),
+            ..Options::tsv()
+        };
+        match read_all("a\t1\nb\t\n", options) {
+            Err(FileError::Parse { line, column, text }) => {
+                assert_eq!((line, column, text.as_str()), (2, 2, ""))
+            }
+            other => panic!("unexpected result {other:?}"),
+        }
+    }
+
+    #[test]
+    fn rejects_non_finite_values() {
+        for value in ["NaN", "inf", "-INF"] {
+            let file = format!("x,y\n0,1\n\n1,{value}\n");
+            match read_all(&file, with_header()) {
+                Err(FileError::Line {
+                    line: 4,
+                    source:
+                        DatasetError::Invalid {
+                            point: 1,
+                            source: ValidationError::NonFinite { index: 1, .. },
+                        },
+                }) => {}
+                other => panic!("{value}: unexpected result {other:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_ragged_rows() {
+        match read_all("x,y\n0,1\n\n2,3,4\n", with_header()) {
+            Err(FileError::Line {
+                line: 4,
+                source:
+                    DatasetError::Dimension {
+                        point: 1,
+                        expected: 2,
+                        found: 3,
+                    },
+            }) => {}
+            other => panic!("unexpected result {other:?}"),
+        }
+    }
+
+    #[test]
+    fn rejects_missing_id() {
+        let options = Options {
+            id_column: Some(2),
+            ..Options::csv()
+        };
+        let records = read_all("0,1,a\n", options.clone()).unwrap();
+        assert_eq!(records[0].id.as_deref(), Some("a"));
+        match read_all("0,1,a\n\n2,3\n", options) {
+            Err(FileError::Format(message)) => {
+                assert_eq!(message, "line 3 has no identifier column")
+            }
+            other => panic!("unexpected result {other:?}"),
+        }
+    }
+}
@@ -0,0 +1,511 @@
+//! Datasets stored as a single contiguous buffer.
+
+#[cfg(feature = "rkyv")]
+use std::fmt;
+use std::iter::FusedIterator;
+use std::ops::{Bound, RangeBounds};
+use std::slice::ChunksExact;
+
+#[cfg(feature = "rkyv")]
+use rkyv::{
+    bytecheck::Verify,
+    rancor::{Fallible, Source},
+};
+#[cfg(feature = "serde")]
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{validate, Coordinates, DatasetError, Element, FastEuclidean};
+#[cfg(feature = "rkyv")]
+use crate::{ArchiveElement, FileError};
+
+/// A set of points of the same dimension, stored row-major in a single buffer.
+///
+/// Storing a dataset as `Vec<FastEuclidean<Vec<f64>>>` costs one allocation per point and
+/// scatters the points across the heap. `FastEuclideanDataset` keeps every coordinate in one
+/// `Vec`, and hands out points as [`FastEuclidean<&[E]>`](FastEuclidean) views which implement
+/// [`Metric`](bitpart::metric::Metric).
+///
+/// Every point is validated as in [`FastEuclidean::try_new`] when it is added. With the `serde`
+/// feature the dataset is serialized as its dimension and flat buffer, and validated again when
+/// deserialized. With the `rkyv` feature it can also be archived, and used in place as an
+/// [`ArchivedFastEuclideanDataset`](crate::ArchivedFastEuclideanDataset).
+/// # Example
+/// ```
+/// # use bitpart::metric::Metric;
+/// # use bitpart_fast_euclidean::FastEuclideanDataset;
+/// let mut dataset = FastEuclideanDataset::new(2);
+/// dataset.push(&[0.0, 0.0]).unwrap();
+/// dataset.push(&[3.0, 4.0]).unwrap();
+///
+/// let (a, b) = (dataset.get(0).unwrap(), dataset.get(1).unwrap());
+/// assert_eq!(a.distance(&b), 5.0);
+/// assert!(dataset.push(&[1.0]).is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize),
+    rkyv(bytecheck(verify))
+)]
+pub struct FastEuclideanDataset<E = f64> {
+    dimension: usize,
+    data: Vec<E>,
+}
+
+/// A borrowed range of points from a [`FastEuclideanDataset`], or any other row-major buffer of
+/// validated points.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FastEuclideanDatasetView<'a, E = f64> {
+    dimension: usize,
+    data: &'a [E],
+}
+
+impl<E> FastEuclideanDataset<E>
+where
+    E: Element,
+{
+    /// Creates an empty dataset of points with `dimension` coordinates.
+    /// # Panics
+    /// Panics if `dimension` is zero.
+    pub fn new(dimension: usize) -> Self {
+        Self::with_capacity(dimension, 0)
+    }
+
+    /// Creates an empty dataset with room for `capacity` points of `dimension` coordinates.
+    /// # Panics
+    /// Panics if `dimension` is zero.
+    pub fn with_capacity(dimension: usize, capacity: usize) -> Self {
+        assert_ne!(dimension, 0, "dimension must be non-zero");
+        Self {
+            dimension,
+            data: Vec::with_capacity(dimension * capacity),
+        }
+    }
+
+    /// Creates a dataset from a row-major buffer of points with `dimension` coordinates,
+    /// validating every point.
+    /// # Panics
+    /// Panics if `dimension` is zero.
+    pub fn from_flat(dimension: usize, data: Vec<E>) -> Result<Self, DatasetError> {
+        assert_ne!(dimension, 0, "dimension must be non-zero");
+        check_flat(dimension, &data)?;
+        Ok(Self { dimension, data })
+    }
+
+    /// Creates a dataset from a row-major buffer of points with `dimension` coordinates, without
+    /// validating them.
+    /// # Safety
+    /// Every point must satisfy the requirements of [`FastEuclidean::new`], and `data.len()`
+    /// must be a multiple of `dimension`.
+    pub unsafe fn from_flat_unchecked(dimension: usize, data: Vec<E>) -> Self {
+        Self { dimension, data }
+    }
+
+    /// Appends a point to the dataset.
+    ///
+    /// The dataset is left unchanged if the point has the wrong dimension or is invalid.
+    pub fn push<C>(&mut self, point: &C) -> Result<(), DatasetError>
+    where
+        C: Coordinates<Element = E> + ?Sized,
+    {
+        let coordinates = point.coordinates();
+        let index = self.len();
+        if coordinates.len() != self.dimension {
+            return Err(DatasetError::Dimension {
+                point: index,
+                expected: self.dimension,
+                found: coordinates.len(),
+            });
+        }
+        validate(coordinates).map_err(|source| DatasetError::Invalid {
+            point: index,
+            source,
+        })?;
+        self.data.extend_from_slice(coordinates);
+        Ok(())
+    }
+
+    /// Appends every point from `points` to the dataset.
+    ///
+    /// Stops at the first point with the wrong dimension or an invalid coordinate. Points before
+    /// it are kept.
+    pub fn try_extend<I>(&mut self, points: I) -> Result<(), DatasetError>
+    where
+        I: IntoIterator,
+        I::Item: Coordinates<Element = E>,
+    {
+        let mut points = points.into_iter();
+        self.data
+            .reserve(points.size_hint().0.saturating_mul(self.dimension));
+        points.try_for_each(|point| self.push(&point))
+    }
+
+    /// Returns a view of the whole dataset.
+    pub fn view(&self) -> FastEuclideanDatasetView<'_, E> {
+        FastEuclideanDatasetView {
+            dimension: self.dimension,
+            data: &self.data,
+        }
+    }
+
+    /// Returns the number of coordinates of each point.
+    pub fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    /// Returns the number of points in the dataset.
+    pub fn len(&self) -> usize {
+        self.data.len() / self.dimension
+    }
+
+    /// Returns `true` if the dataset contains no points.
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Returns the point at `index`, or `None` if it is out of bounds.
+    pub fn get(&self, index: usize) -> Option<FastEuclidean<&[E]>> {
+        self.view().get(index)
+    }
+
+    /// Returns a view of the points within `range`.
+    /// # Panics
+    /// Panics if `range` is out of bounds.
+    pub fn slice<R>(&self, range: R) -> FastEuclideanDatasetView<'_, E>
+    where
+        R: RangeBounds<usize>,
+    {
+        self.view().slice(range)
+    }
+
+    /// Returns an iterator over the points in the dataset.
+    pub fn iter(&self) -> Points<'_, E> {
+        self.view().iter()
+    }
+
+    /// Returns the coordinates of every point, row-major.
+    pub fn as_flat(&self) -> &[E] {
+        &self.data
+    }
+
+    /// Consumes the dataset, returning the row-major coordinate buffer.
+    pub fn into_flat(self) -> Vec<E> {
+        self.data
+    }
+}
+
+impl<E, T> Extend<FastEuclidean<T>> for FastEuclideanDataset<E>
+where
+    E: Element,
+    T: Coordinates<Element = E>,
+{
+    /// Appends already validated points to the dataset.
+    /// # Panics
+    /// Panics if a point has the wrong dimension.
+    fn extend<I: IntoIterator<Item = FastEuclidean<T>>>(&mut self, points: I) {
+        for point in points {
+            let coordinates = point.coordinates();
+            assert_eq!(
+                coordinates.len(),
+                self.dimension,
+                "point has the wrong dimension"
+            );
+            self.data.extend_from_slice(coordinates);
+        }
+    }
+}
+
+impl<'a, E> IntoIterator for &'a FastEuclideanDataset<E>
+where
+    E: Element,
+{
+    type Item = FastEuclidean<&'a [E]>;
+    type IntoIter = Points<'a, E>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, E> FastEuclideanDatasetView<'a, E>
+where
+    E: Element,
+{
+    /// Creates a view over a row-major buffer of points with `dimension` coordinates, without
+    /// validating them.
+    /// # Safety
+    /// Every point must satisfy the requirements of [`FastEuclidean::new`], and `data.len()`
+    /// must be a multiple of `dimension`, which must be non-zero.
+    pub unsafe fn from_flat_unchecked(dimension: usize, data: &'a [E]) -> Self {
+        Self { dimension, data }
+    }
+
+    /// Returns the number of coordinates of each point.
+    pub fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    /// Returns the number of points in the view.
+    pub fn len(&self) -> usize {
+        self.data.len() / self.dimension
+    }
+
+    /// Returns `true` if the view contains no points.
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Returns the point at `index`, or `None` if it is out of bounds.
+    pub fn get(&self, index: usize) -> Option<FastEuclidean<&'a [E]>> {
+        let start = index.checked_mul(self.dimension)?;
+        let coordinates = self.data.get(start..start.checked_add(self.dimension)?)?;
+        Some(FastEuclidean(coordinates))
+    }
+
+    /// Returns a view of the points within `range`.
+    /// # Panics
+    /// Panics if `range` is out of bounds.
+    pub fn slice<R>(&self, range: R) -> FastEuclideanDatasetView<'a, E>
+    where
+        R: RangeBounds<usize>,
+    {
+        let start = match range.start_bound() {
+            Bound::Included(&start) => start,
+            Bound::Excluded(&start) => start + 1,
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&end) => end + 1,
+            Bound::Excluded(&end) => end,
+            Bound::Unbounded => self.len(),
+        };
+        FastEuclideanDatasetView {
+            dimension: self.dimension,
+            data: &self.data[start * self.dimension..end * self.dimension],
+        }
+    }
+
+    /// Returns an iterator over the points in the view.
+    pub fn iter(&self) -> Points<'a, E> {
+        Points(self.data.chunks_exact(self.dimension))
+    }
+
+    /// Returns the coordinates of every point, row-major.
+    pub fn as_flat(&self) -> &'a [E] {
+        self.data
+    }
+
+    /// Copies the points into an owned dataset.
+    pub fn to_owned(&self) -> FastEuclideanDataset<E> {
+        FastEuclideanDataset {
+            dimension: self.dimension,
+            data: self.data.to_vec(),
+        }
+    }
+}
+
+impl<'a, E> IntoIterator for FastEuclideanDatasetView<'a, E>
+where
+    E: Element,
+{
+    type Item = FastEuclidean<&'a [E]>;
+    type IntoIter = Points<'a, E>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Iterator over the points of a dataset.
+///
+/// Created by [`FastEuclideanDataset::iter`] and [`FastEuclideanDatasetView::iter`].
+#[derive(Debug, Clone)]
+pub struct Points<'a, E>(ChunksExact<'a, E>);
+
+impl<'a, E> Iterator for Points<'a, E> {
+    type Item = FastEuclidean<&'a [E]>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(FastEuclidean)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        self.0.nth(n).map(FastEuclidean)
+    }
+}
+
+impl<E> DoubleEndedIterator for Points<'_, E> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(FastEuclidean)
+    }
+}
+
+impl<E> ExactSizeIterator for Points<'_, E> {}
+
+impl<E> FusedIterator for Points<'_, E> {}
+
+/// Validates every point of a row-major buffer, and that it holds a whole number of points.
+fn check_flat<E: Element>(dimension: usize, data: &[E]) -> Result<(), DatasetError> {
+    let chunks = data.chunks_exact(dimension);
+    let remainder = chunks.remainder().len();
+    for (point, coordinates) in chunks.enumerate() {
+        validate(coordinates).map_err(|source| DatasetError::Invalid { point, source })?;
+    }
+    if remainder != 0 {
+        return Err(DatasetError::Dimension {
+            point: data.len() / dimension,
+            expected: dimension,
+            found: remainder,
+        });
+    }
+    Ok(())
+}
+
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "FastEuclideanDataset")]
+struct Flat<D> {
+    dimension: usize,
+    data: D,
+}
+
+#[cfg(feature = "serde")]
+impl<E> Serialize for FastEuclideanDataset<E>
+where
+    E: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        Flat {
+            dimension: self.dimension,
+            data: &self.data,
+        }
+        .serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, E> Deserialize<'de> for FastEuclideanDataset<E>
+where
+    E: Element + Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let Flat { dimension, data } = Flat::<Vec<E>>::deserialize(deserializer)?;
+        if dimension == 0 {
+            return Err(D::Error::custom("dimension must be non-zero"));
+        }
+        Self::from_flat(dimension, data).map_err(D::Error::custom)
+    }
+}
+
+/// A [`FastEuclideanDataset`] archived with [`rkyv`], usable in place without deserializing.
+///
+/// Checking an archive with [`rkyv::access`] also validates every point, so a corrupted archive is
+/// rejected rather than violating the safety contract of [`FastEuclidean::new`].
+/// # Example
+/// ```
+/// # use bitpart::metric::Metric;
+/// # use bitpart_fast_euclidean::{ArchivedFastEuclideanDataset, FastEuclideanDataset};
+/// use rkyv::rancor::Error;
+///
+/// let mut dataset = FastEuclideanDataset::new(2);
+/// dataset.push(&[0.0, 0.0]).unwrap();
+/// dataset.push(&[3.0, 4.0]).unwrap();
+/// let bytes = rkyv::to_bytes::<Error>(&dataset).unwrap();
+///
+/// let archived = rkyv::access::<ArchivedFastEuclideanDataset, Error>(&bytes).unwrap();
+/// let view = archived.view();
+/// assert_eq!(view.get(0).unwrap().distance(&view.get(1).unwrap()), 5.0);
+/// ```
+#[cfg(feature = "rkyv")]
+impl<E> ArchivedFastEuclideanDataset<E>
+where
+    E: ArchiveElement,
+{
+    /// Borrows the archived points.
+    pub fn view(&self) -> FastEuclideanDatasetView<'_, E> {
+        // SAFETY: the archive was checked by `verify`, or is trusted by the caller of
+        // `rkyv::access_unchecked`.
+        unsafe {
+            FastEuclideanDatasetView::from_flat_unchecked(
+                self.dimension.to_native() as usize,
+                E::from_archived(self.data.as_slice()),
+            )
+        }
+    }
+}
+
+#[cfg(feature = "rkyv")]
+impl<E> fmt::Debug for ArchivedFastEuclideanDataset<E>
+where
+    E: ArchiveElement + fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let view = self.view();
+        f.debug_struct("ArchivedFastEuclideanDataset")
+            .field("dimension", &view.dimension())
+            .field("data", &view.as_flat())
+            .finish()
+    }
+}
+
+#[cfg(feature = "rkyv")]
+unsafe impl<E, C> Verify<C> for ArchivedFastEuclideanDataset<E>
+where
+    E: ArchiveElement,
+    C: Fallible + ?Sized,
+    C::Error: Source,
+{
+    fn verify(&self, _: &mut C) -> Result<(), C::Error> {
+        let dimension = self.dimension.to_native() as usize;
+        if dimension == 0 {
+            return Err(Source::new(FileError::Format(
+                "dimension must be non-zero".into(),
+            )));
+        }
+        check_flat(dimension, E::from_archived(self.data.as_slice())).map_err(Source::new)
+    }
+}
+
+#[cfg(all(test, feature = "rkyv"))]
+mod tests {
+    use super::*;
+    use rkyv::rancor::Error;
+
+    /// Archives a dataset with the coordinate `4.0` overwritten by `value`.
+    fn corrupted(value: f64) -> rkyv::util::AlignedVec {
+        let mut dataset = FastEuclideanDataset::new(2);
+        dataset.push(&[0.0, 0.0]).unwrap();
+        dataset.push(&[3.0, 4.0]).unwrap();
+        let mut bytes = rkyv::to_bytes::<Error>(&dataset).unwrap();
+
+        let target = 4.0_f64.to_le_bytes();
+        let offset = bytes.windows(8).position(|w| w == target).unwrap();
+        bytes[offset..offset + 8].copy_from_slice(&value.to_le_bytes());
+        bytes
+    }
+
+    #[test]
+    fn access_checks_points() {
+        let bytes = corrupted(4.0);
+        let archived = rkyv::access::<ArchivedFastEuclideanDataset, Error>(&bytes).unwrap();
+        assert_eq!(*archived.view().get(1).unwrap(), [3.0, 4.0]);
+
+        for value in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY, 1e300] {
+            let bytes = corrupted(value);
+            assert!(
+                rkyv::access::<ArchivedFastEuclideanDataset, Error>(&bytes).is_err(),
+                "{value} was accepted"
+            );
+        }
+    }
+}
@@ -0,0 +1,263 @@
+use crate::kernel::{self, Reduction, MANY};
+
+#[cfg(feature = "half")]
+use half::{bf16, f16, slice::HalfFloatSliceExt};
+
+/// Coordinate types supported by the fast metrics.
+///
+/// Distances between `f64` coordinates are accumulated in `f64`, and distances between `f32`
+/// coordinates in `f32`. Either way the result is widened to the `f64` required by
+/// [`Metric`](bitpart::metric::Metric).
+///
+/// With the `half` feature, [`f16`](half::f16) and [`bf16`](half::bf16) coordinates are also
+/// supported. They are widened to `f32` a block at a time inside the distance loop, so points can
+/// be stored at half the size of `f32` points without losing accumulation precision.
+/// # Example
+/// ```
+/// # #[cfg(feature = "half")] {
+/// # use bitpart::metric::Metric;
+/// # use bitpart_fast_euclidean::FastEuclidean;
+/// use half::f16;
+///
+/// let a: Vec<f64> = (0..960).map(|i| (i as f64 * 0.37).sin()).collect();
+/// let b: Vec<f64> = (0..960).map(|i| (i as f64 * 0.71).cos()).collect();
+/// let exact = FastEuclidean::try_new(a.clone())
+///     .unwrap()
+///     .distance(&FastEuclidean::try_new(b.clone()).unwrap());
+///
+/// let a = FastEuclidean::try_new(a.into_iter().map(f16::from_f64).collect::<Vec<_>>()).unwrap();
+/// let b = FastEuclidean::try_new(b.into_iter().map(f16::from_f64).collect::<Vec<_>>()).unwrap();
+///
+/// // Rounding each coordinate to `f16` dominates the error, not the accumulation.
+/// assert!((a.distance(&b) - exact).abs() / exact < 1e-3);
+/// # }
+/// ```
+///
+/// This trait is sealed, as the kernels for each element type live in this crate.
+pub trait Element: Copy + Send + Sync + 'static + private::Sealed {
+    /// Widens the element to `f64`.
+    fn to_f64(self) -> f64;
+}
+
+/// Coordinate types that can be used in place in an [`rkyv`] archive.
+///
+/// Archives store values little-endian, so this is only implemented for `f32` and `f64` on
+/// little-endian targets.
+#[cfg(feature = "rkyv")]
+pub trait ArchiveElement: Element + rkyv::Archive {
+    /// Reinterprets archived values as native ones.
+    fn from_archived(archived: &[Self::Archived]) -> &[Self];
+}
+
+pub(crate) mod private {
+    /// Kernels specific to an element type.
+    pub trait Sealed: Sized {
+        /// Largest value the accumulator used by [`squared_euclidean`](Self::squared_euclidean)
+        /// can hold.
+        const ACCUMULATOR_MAX: f64;
+
+        /// Identifies the element type in file headers.
+        const TYPE_CODE: u32;
+
+        /// Converts from `f64`, rounding to the nearest representable value.
+        fn from_f64(value: f64) -> Self;
+
+        /// Sum of squared differences, accumulated in the element's native precision.
+        fn squared_euclidean(a: &[Self], b: &[Self]) -> f64;
+
+        /// Sums of squared differences between `query` and each of `targets`, accumulated as in
+        /// [`squared_euclidean`](Self::squared_euclidean).
+        fn squared_euclidean_many(
+            query: &[Self],
+            targets: [&[Self]; super::MANY],
+        ) -> [f64; super::MANY];
+
+        /// Reduces the coordinate-wise differences with `R`, accumulated as in
+        /// [`squared_euclidean`](Self::squared_euclidean).
+        fn reduce<R: super::Reduction>(a: &[Self], b: &[Self]) -> f64;
+    }
+}
+
+impl Element for f64 {
+    fn to_f64(self) -> f64 {
+        self
+    }
+}
+
+impl private::Sealed for f64 {
+    const ACCUMULATOR_MAX: f64 = f64::MAX;
+    const TYPE_CODE: u32 = 2;
+
+    fn from_f64(value: f64) -> Self {
+        value
+    }
+
+    fn squared_euclidean(a: &[Self], b: &[Self]) -> f64 {
+        kernel::squared_euclidean_f64(a, b)
+    }
+
+    fn squared_euclidean_many(query: &[Self], targets: [&[Self]; MANY]) -> [f64; MANY] {
+        kernel::squared_euclidean_many_f64(query, targets)
+    }
+
+    fn reduce<R: Reduction>(a: &[Self], b: &[Self]) -> f64 {
+        kernel::reduce::<R, f64>(a, b)
+    }
+}
+
+impl Element for f32 {
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+}
+
+impl private::Sealed for f32 {
+    const ACCUMULATOR_MAX: f64 = f32::MAX as f64;
+    const TYPE_CODE: u32 = 1;
+
+    fn from_f64(value: f64) -> Self {
+        value as f32
+    }
+
+    fn squared_euclidean(a: &[Self], b: &[Self]) -> f64 {
+        kernel::squared_euclidean_f32(a, b) as f64
+    }
+
+    fn squared_euclidean_many(query: &[Self], targets: [&[Self]; MANY]) -> [f64; MANY] {
+        kernel::squared_euclidean_many_f32(query, targets).map(f64::from)
+    }
+
+    fn reduce<R: Reduction>(a: &[Self], b: &[Self]) -> f64 {
+        kernel::reduce::<R, f32>(a, b) as f64
+    }
+}
+
+macro_rules! impl_archive {
+    ($($t:ty => $archived:ty),*) => {
+        $(
+            #[cfg(all(feature = "rkyv", target_endian = "little"))]
+            impl ArchiveElement for $t {
+                fn from_archived(archived: &[Self::Archived]) -> &[Self] {
+                    // Fails to compile if rkyv is configured for a different layout, such as
+                    // big-endian or unaligned archives.
+                    let archived: &[$archived] = archived;
+                    // SAFETY: on little-endian targets, the archived type is a wrapper with the
+                    // same size, alignment and representation as the native one.
+                    unsafe { std::slice::from_raw_parts(archived.as_ptr().cast(), archived.len()) }
+                }
+            }
+        )*
+    };
+}
+
+impl_archive!(f32 => rkyv::rend::f32_le, f64 => rkyv::rend::f64_le);
+
+/// Number of half-precision coordinates widened to `f32` at a time.
+#[cfg(feature = "half")]
+const HALF_BLOCK: usize = 256;
+
+/// Sum of squared differences between half-precision slices, widening each block to `f32`.
+///
+/// Each block is reduced by the `f32` kernel, and the block sums are accumulated in `f64`.
+#[cfg(feature = "half")]
+fn squared_euclidean_half<H>(a: &[H], b: &[H]) -> f64
+where
+    [H]: HalfFloatSliceExt,
+{
+    let len = a.len().min(b.len());
+    let (mut x, mut y) = ([0.0; HALF_BLOCK], [0.0; HALF_BLOCK]);
+    a[..len]
+        .chunks(HALF_BLOCK)
+        .zip(b[..len].chunks(HALF_BLOCK))
+        .map(|(a, b)| {
+            let (x, y) = (&mut x[..a.len()], &mut y[..b.len()]);
+            a.convert_to_f32_slice(x);
+            b.convert_to_f32_slice(y);
+            kernel::squared_euclidean_f32(x, y) as f64
+        })
+        .sum()
+}
+
+/// One-to-many counterpart of [`squared_euclidean_half`].
+#[cfg(feature = "half")]
+fn squared_euclidean_many_half<H>(query: &[H], targets: [&[H]; MANY]) -> [f64; MANY]
+where
+    [H]: HalfFloatSliceExt,
+{
+    let len = targets.iter().fold(query.len(), |len, t| len.min(t.len()));
+    let mut x = [0.0; HALF_BLOCK];
+    let mut y = [[0.0; HALF_BLOCK]; MANY];
+    let mut sums = [0.0; MANY];
+    for start in (0..len).step_by(HALF_BLOCK) {
+        let end = len.min(start + HALF_BLOCK);
+        let x = &mut x[..end - start];
+        query[start..end].convert_to_f32_slice(x);
+        for (y, target) in y.iter_mut().zip(targets) {
+            target[start..end].convert_to_f32_slice(&mut y[..end - start]);
+        }
+        let partial =
+            kernel::squared_euclidean_many_f32(x, y.each_ref().map(|y| &y[..end - start]));
+        for (sum, partial) in sums.iter_mut().zip(partial) {
+            *sum += partial as f64;
+        }
+    }
+    sums
+}
+
+/// Counterpart of [`squared_euclidean_half`] for any [`Reduction`].
+#[cfg(feature = "half")]
+fn reduce_half<H, R>(a: &[H], b: &[H]) -> f64
+where
+    [H]: HalfFloatSliceExt,
+    R: Reduction,
+{
+    let len = a.len().min(b.len());
+    let (mut x, mut y) = ([0.0; HALF_BLOCK], [0.0; HALF_BLOCK]);
+    a[..len]
+        .chunks(HALF_BLOCK)
+        .zip(b[..len].chunks(HALF_BLOCK))
+        .map(|(a, b)| {
+            let (x, y) = (&mut x[..a.len()], &mut y[..b.len()]);
+            a.convert_to_f32_slice(x);
+            b.convert_to_f32_slice(y);
+            kernel::reduce::<R, f32>(x, y) as f64
+        })
+        .fold(0.0, R::combine)
+}
+
+macro_rules! impl_half {
+    ($($t:ty => $code:expr),*) => {
+        $(
+            #[cfg(feature = "half")]
+            impl Element for $t {
+                fn to_f64(self) -> f64 {
+                    <$t>::to_f64(self)
+                }
+            }
+
+            #[cfg(feature = "half")]
+            impl private::Sealed for $t {
+                const ACCUMULATOR_MAX: f64 = f32::MAX as f64;
+                const TYPE_CODE: u32 = $code;
+
+                fn from_f64(value: f64) -> Self {
+                    <$t>::from_f64(value)
+                }
+
+                fn squared_euclidean(a: &[Self], b: &[Self]) -> f64 {
+                    squared_euclidean_half(a, b)
+                }
+
+                fn squared_euclidean_many(query: &[Self], targets: [&[Self]; MANY]) -> [f64; MANY] {
+                    squared_euclidean_many_half(query, targets)
+                }
+
+                fn reduce<R: Reduction>(a: &[Self], b: &[Self]) -> f64 {
+                    reduce_half::<Self, R>(a, b)
+                }
+            }
+        )*
+    };
+}
+
+impl_half!(f16 => 3, bf16 => 4);
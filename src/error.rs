@@ -0,0 +1,275 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+/// Error returned when a point cannot be safely wrapped in a fast metric.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ValidationError {
+    /// The coordinate at `index` is [`NaN`](f64::NAN) or infinite.
+    NonFinite { index: usize, value: f64 },
+    /// The coordinate at `index` is so large in magnitude that the distance to another valid point
+    /// of the same dimension could overflow. `limit` is the largest magnitude accepted.
+    OutOfRange {
+        index: usize,
+        value: f64,
+        limit: f64,
+    },
+    /// The weight at `index` is negative, [`NaN`](f64::NAN) or infinite.
+    InvalidWeight { index: usize, value: f64 },
+    /// The point has `found` coordinates, but the metric it is wrapped for, such as a set of
+    /// [`Weights`](crate::Weights) or a [`Whitening`](crate::Whitening) transform, has dimension
+    /// `expected`.
+    Dimension { expected: usize, found: usize },
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationError::NonFinite { index, value } => {
+                write!(f, "coordinate {index} is not finite ({value})")
+            }
+            ValidationError::OutOfRange {
+                index,
+                value,
+                limit,
+            } => write!(
+                f,
+                "coordinate {index} is out of range ({value:e}, magnitude must not exceed {limit:e})"
+            ),
+            ValidationError::InvalidWeight { index, value } => {
+                write!(f, "weight {index} is not finite and non-negative ({value})")
+            }
+            ValidationError::Dimension { expected, found } => {
+                write!(f, "point has {found} coordinates, expected {expected}")
+            }
+        }
+    }
+}
+
+impl Error for ValidationError {}
+
+/// Error returned when comparing two points of different dimensions.
+///
+/// [`distance`](bitpart::metric::Metric::distance) silently ignores the trailing coordinates of the
+/// longer point, and only asserts on a mismatch in debug builds, so the fast metrics also provide
+/// a `try_distance` method that returns this error instead.
+/// # Example
+/// ```
+/// # use bitpart_fast_euclidean::{DimensionMismatch, FastEuclidean};
+/// let point1 = FastEuclidean::<Vec<f64>>::try_new(vec![0.0, 0.0]).unwrap();
+/// let point2 = FastEuclidean::<Vec<f64>>::try_new(vec![1.0, 1.0, 1.0]).unwrap();
+///
+/// assert_eq!(
+///     point1.try_distance(&point2),
+///     Err(DimensionMismatch { left: 2, right: 3 })
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DimensionMismatch {
+    /// Dimension of the left-hand point.
+    pub left: usize,
+    /// Dimension of the right-hand point.
+    pub right: usize,
+}
+
+impl fmt::Display for DimensionMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "dimension mismatch ({} and {} coordinates)",
+            self.left, self.right
+        )
+    }
+}
+
+impl Error for DimensionMismatch {}
+
+/// Error returned when comparing two points whose metric carries its own parameters, such as
+/// [`FastWeightedEuclidean`](crate::FastWeightedEuclidean) or
+/// [`FastMinkowskiDyn`](crate::FastMinkowskiDyn).
+///
+/// Each point is validated against its own parameters, so two points can only be compared if
+/// their parameters are the same.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PointMismatch {
+    /// The points have different dimensions.
+    Dimension(DimensionMismatch),
+    /// The points were wrapped with different [`Weights`](crate::Weights).
+    Weights,
+    /// The points were wrapped with different Minkowski exponents.
+    Exponent { left: f64, right: f64 },
+}
+
+impl fmt::Display for PointMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PointMismatch::Dimension(e) => e.fmt(f),
+            PointMismatch::Weights => write!(f, "points have different weights"),
+            PointMismatch::Exponent { left, right } => {
+                write!(f, "points have different exponents ({left} and {right})")
+            }
+        }
+    }
+}
+
+impl Error for PointMismatch {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            PointMismatch::Dimension(e) => Some(e),
+            PointMismatch::Weights | PointMismatch::Exponent { .. } => None,
+        }
+    }
+}
+
+impl From<DimensionMismatch> for PointMismatch {
+    fn from(e: DimensionMismatch) -> Self {
+        PointMismatch::Dimension(e)
+    }
+}
+
+/// Error returned when adding a point to a dataset.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DatasetError {
+    /// The point at index `point` has `found` coordinates, but the dataset has dimension
+    /// `expected`.
+    Dimension {
+        point: usize,
+        expected: usize,
+        found: usize,
+    },
+    /// The point at index `point` does not uphold the fast metric safety contract.
+    Invalid {
+        point: usize,
+        source: ValidationError,
+    },
+}
+
+impl fmt::Display for DatasetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DatasetError::Dimension {
+                point,
+                expected,
+                found,
+            } => write!(
+                f,
+                "point {point} has {found} coordinates, expected {expected}"
+            ),
+            DatasetError::Invalid { point, source } => write!(f, "point {point}: {source}"),
+        }
+    }
+}
+
+impl Error for DatasetError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DatasetError::Dimension { .. } => None,
+            DatasetError::Invalid { source, .. } => Some(source),
+        }
+    }
+}
+
+/// Error returned when fitting a [`Whitening`](crate::Whitening) transform.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WhiteningError {
+    /// The sample contains no points.
+    Empty,
+    /// The points of the sample have no coordinates.
+    ZeroDimension,
+    /// A point in the sample is invalid, or has a different dimension from the first.
+    Dataset(DatasetError),
+    /// The covariance matrix of the sample is not positive-definite, so it has no Cholesky
+    /// factor. This happens when some coordinate is constant, or a linear combination of the
+    /// others, across the sample. `column` is the first column found to be degenerate.
+    NotPositiveDefinite { column: usize },
+}
+
+impl fmt::Display for WhiteningError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WhiteningError::Empty => write!(f, "the sample contains no points"),
+            WhiteningError::ZeroDimension => write!(f, "the sample points have no coordinates"),
+            WhiteningError::Dataset(e) => write!(f, "invalid sample: {e}"),
+            WhiteningError::NotPositiveDefinite { column } => write!(
+                f,
+                "covariance matrix is not positive-definite (degenerate column {column})"
+            ),
+        }
+    }
+}
+
+impl Error for WhiteningError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            WhiteningError::Dataset(e) => Some(e),
+            WhiteningError::Empty
+            | WhiteningError::ZeroDimension
+            | WhiteningError::NotPositiveDefinite { .. } => None,
+        }
+    }
+}
+
+impl From<DatasetError> for WhiteningError {
+    fn from(e: DatasetError) -> Self {
+        WhiteningError::Dataset(e)
+    }
+}
+
+/// Error returned when reading or writing a dataset file.
+#[derive(Debug)]
+pub enum FileError {
+    /// The underlying reader or writer failed.
+    Io(io::Error),
+    /// The file does not follow the expected format.
+    Format(String),
+    /// The file ends partway through the point at index `point`.
+    Truncated { point: usize },
+    /// The field in 1-based `column` of 1-based `line` is not a number.
+    Parse {
+        line: usize,
+        column: usize,
+        text: String,
+    },
+    /// The point on 1-based `line` of a text file is invalid, or has the wrong dimension.
+    Line { line: usize, source: DatasetError },
+    /// A point in the file is invalid, or has the wrong dimension.
+    Dataset(DatasetError),
+}
+
+impl fmt::Display for FileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FileError::Io(e) => write!(f, "i/o error: {e}"),
+            FileError::Format(message) => write!(f, "malformed file: {message}"),
+            FileError::Truncated { point } => write!(f, "file ends partway through point {point}"),
+            FileError::Parse { line, column, text } => {
+                write!(f, "line {line}, column {column}: {text:?} is not a number")
+            }
+            FileError::Line { line, source } => write!(f, "line {line}: {source}"),
+            FileError::Dataset(e) => write!(f, "invalid dataset: {e}"),
+        }
+    }
+}
+
+impl Error for FileError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            FileError::Io(e) => Some(e),
+            FileError::Format(_) | FileError::Truncated { .. } | FileError::Parse { .. } => None,
+            FileError::Line { source, .. } => Some(source),
+            FileError::Dataset(e) => Some(e),
+        }
+    }
+}
+
+impl From<io::Error> for FileError {
+    fn from(e: io::Error) -> Self {
+        FileError::Io(e)
+    }
+}
+
+impl From<DatasetError> for FileError {
+    fn from(e: DatasetError) -> Self {
+        FileError::Dataset(e)
+    }
+}
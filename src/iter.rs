@@ -0,0 +1,161 @@
+use bitpart::metric::Metric;
+use std::ops::Deref;
+
+#[cfg(feature = "serde")]
+use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{check_coordinate, coordinate_limit, kernel, ValidationError};
+
+/// Wrapper struct to apply Euclidean distance to points whose coordinates are not contiguous.
+///
+/// [`FastEuclidean`](crate::FastEuclidean) requires its points to expose their coordinates as a
+/// slice through [`Coordinates`](crate::Coordinates), so that its kernels can process several at
+/// a time. `FastEuclideanIter` accepts any `T` whose references iterate over `f64` coordinates,
+/// such as a [`VecDeque`](std::collections::VecDeque), at the cost of a scalar fold. It has the
+/// same constructor contract as `FastEuclidean`, and the same `IntoIterator` and serde impls.
+///
+/// Before [`Coordinates`](crate::Coordinates) was introduced, `FastEuclidean` itself accepted any
+/// such container. Those points must now be wrapped in `FastEuclideanIter` instead.
+/// # Example
+/// ```
+/// # use bitpart::metric::Metric;
+/// # use bitpart_fast_euclidean::FastEuclideanIter;
+/// # use std::collections::VecDeque;
+/// let point1 = FastEuclideanIter::<VecDeque<f64>>::try_new(VecDeque::from([0.0, 0.0])).unwrap();
+/// let point2 = FastEuclideanIter::<VecDeque<f64>>::try_new(VecDeque::from([3.0, 4.0])).unwrap();
+///
+/// assert_eq!(point1.distance(&point2), 5.0);
+/// ```
+#[derive(Debug, Clone)]
+pub struct FastEuclideanIter<T>(T);
+
+impl<T> FastEuclideanIter<T> {
+    /// Creates a new `FastEuclideanIter`.
+    /// # Safety
+    /// The same requirements as [`FastEuclidean::new`](crate::FastEuclidean::new) apply.
+    pub unsafe fn new(t: T) -> Self {
+        Self(t)
+    }
+
+    /// Returns the wrapped point.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> FastEuclideanIter<T>
+where
+    for<'a> &'a T: IntoIterator<Item = &'a f64>,
+{
+    /// Creates a new `FastEuclideanIter`, checking the requirements of
+    /// [`FastEuclidean::try_new`](crate::FastEuclidean::try_new).
+    pub fn try_new(t: T) -> Result<Self, ValidationError> {
+        let limit = coordinate_limit::<f64>((&t).into_iter().count());
+        (&t).into_iter()
+            .enumerate()
+            .try_for_each(|(index, &value)| check_coordinate(index, value, limit))?;
+        Ok(Self(t))
+    }
+}
+
+impl<T> Deref for FastEuclideanIter<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> IntoIterator for FastEuclideanIter<T>
+where
+    T: IntoIterator,
+{
+    type Item = <T as IntoIterator>::Item;
+    type IntoIter = <T as IntoIterator>::IntoIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a FastEuclideanIter<T>
+where
+    &'a T: IntoIterator,
+{
+    type Item = <&'a T as IntoIterator>::Item;
+    type IntoIter = <&'a T as IntoIterator>::IntoIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+/// `Clone` is required by [`Metric`] itself.
+impl<T> Metric for FastEuclideanIter<T>
+where
+    for<'a> &'a T: IntoIterator<Item = &'a f64>,
+    T: Clone,
+{
+    fn distance(&self, rhs: &FastEuclideanIter<T>) -> f64 {
+        kernel::squared_euclidean_iter(&self.0, &rhs.0).sqrt()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T> Serialize for FastEuclideanIter<T>
+where
+    T: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.0.serialize(serializer)
+    }
+}
+
+/// Points are validated as in `try_new`. Trusted inputs can skip validation with
+/// [`unchecked::deserialize`](crate::unchecked::deserialize).
+#[cfg(feature = "serde")]
+impl<'de, T> Deserialize<'de> for FastEuclideanIter<T>
+where
+    T: Deserialize<'de>,
+    for<'a> &'a T: IntoIterator<Item = &'a f64>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Self::try_new(T::deserialize(deserializer)?).map_err(D::Error::custom)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T> crate::unchecked::Unchecked<'de> for FastEuclideanIter<T>
+where
+    T: Deserialize<'de>,
+{
+    unsafe fn deserialize_unchecked<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ok(Self::new(T::deserialize(deserializer)?))
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+
+    #[test]
+    fn serde_validates() {
+        let point: FastEuclideanIter<VecDeque<f64>> = serde_json::from_str("[3.0, 4.0]").unwrap();
+        assert_eq!(serde_json::to_string(&point).unwrap(), "[3.0,4.0]");
+        assert_eq!((&point).into_iter().sum::<f64>(), 7.0);
+        assert_eq!(point.into_iter().collect::<Vec<_>>(), [3.0, 4.0]);
+
+        let error = serde_json::from_str::<FastEuclideanIter<VecDeque<f64>>>("[0.0, 1e300]");
+        assert!(error.unwrap_err().to_string().contains("out of range"));
+    }
+}
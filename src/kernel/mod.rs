@@ -0,0 +1,651 @@
+//! Reductions shared by the fast metrics.
+//!
+//! Coordinates are processed in chunks of [`LANES`], followed by a scalar tail. With the `nightly`
+//! feature each chunk is a [`std::simd`] vector and the tail uses the `fast` float intrinsics,
+//! which allow the compiler to reassociate the sum freely. On stable each chunk is spread across
+//! independent accumulators instead, which breaks the dependency chain between additions and
+//! lets the compiler vectorise the loop without permission to reassociate.
+//!
+//! On x86-64 the portable kernels are replaced at runtime by hand-written SSE2, AVX2+FMA or
+//! AVX-512 kernels, depending on what the CPU supports. Detection happens once, on first use.
+
+use std::fmt;
+use std::sync::OnceLock;
+
+use crate::Element;
+
+#[cfg(feature = "nightly")]
+use std::intrinsics::{fadd_fast, fmul_fast, fsub_fast};
+#[cfg(feature = "nightly")]
+use std::simd::{num::SimdFloat, Simd};
+
+#[cfg(target_arch = "x86_64")]
+mod x86;
+
+/// Number of coordinates processed per chunk.
+const LANES: usize = 8;
+
+/// Number of targets compared against a query per pass by the one-to-many kernels.
+pub(crate) const MANY: usize = 4;
+
+/// Family of distance kernels selected for the current CPU.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Kernel {
+    /// Chunked kernels left to the compiler to vectorise for the build target.
+    Portable,
+    /// Explicit SSE2 kernels.
+    Sse2,
+    /// Explicit AVX2 kernels using fused multiply-add.
+    Avx2Fma,
+    /// Explicit AVX-512 kernels.
+    Avx512,
+}
+
+impl fmt::Display for Kernel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Kernel::Portable => "portable",
+            Kernel::Sse2 => "sse2",
+            Kernel::Avx2Fma => "avx2+fma",
+            Kernel::Avx512 => "avx512f",
+        })
+    }
+}
+
+/// Returns the kernel family used by the fast metrics on this CPU.
+///
+/// The CPU is inspected on the first call (or first distance computation), and the result is
+/// reused for the lifetime of the process.
+/// # Example
+/// ```
+/// println!("distance kernel: {}", bitpart_fast_euclidean::active_kernel());
+/// ```
+pub fn active_kernel() -> Kernel {
+    dispatch().kernel
+}
+
+/// Kernels selected for the current CPU.
+///
+/// # Safety
+/// The kernels must only be called if the CPU supports the features required by `kernel`.
+struct Dispatch {
+    kernel: Kernel,
+    squared_euclidean_f64: unsafe fn(&[f64], &[f64]) -> f64,
+    squared_euclidean_f32: unsafe fn(&[f32], &[f32]) -> f32,
+    squared_euclidean_many_f64: unsafe fn(&[f64], [&[f64]; MANY]) -> [f64; MANY],
+    squared_euclidean_many_f32: unsafe fn(&[f32], [&[f32]; MANY]) -> [f32; MANY],
+}
+
+fn dispatch() -> &'static Dispatch {
+    static DISPATCH: OnceLock<Dispatch> = OnceLock::new();
+    DISPATCH.get_or_init(detect)
+}
+
+#[cfg(target_arch = "x86_64")]
+fn detect() -> Dispatch {
+    if is_x86_feature_detected!("avx512f") {
+        Dispatch {
+            kernel: Kernel::Avx512,
+            squared_euclidean_f64: x86::squared_euclidean_f64_avx512,
+            squared_euclidean_f32: x86::squared_euclidean_f32_avx512,
+            squared_euclidean_many_f64: x86::squared_euclidean_many_f64_avx512,
+            squared_euclidean_many_f32: x86::squared_euclidean_many_f32_avx512,
+        }
+    } else if is_x86_feature_detected!("avx2") && is_x86_feature_detected!("fma") {
+        Dispatch {
+            kernel: Kernel::Avx2Fma,
+            squared_euclidean_f64: x86::squared_euclidean_f64_avx2_fma,
+            squared_euclidean_f32: x86::squared_euclidean_f32_avx2_fma,
+            squared_euclidean_many_f64: x86::squared_euclidean_many_f64_avx2_fma,
+            squared_euclidean_many_f32: x86::squared_euclidean_many_f32_avx2_fma,
+        }
+    } else {
+        Dispatch {
+            kernel: Kernel::Sse2,
+            squared_euclidean_f64: x86::squared_euclidean_f64_sse2,
+            squared_euclidean_f32: x86::squared_euclidean_f32_sse2,
+            squared_euclidean_many_f64: squared_euclidean_many_portable,
+            squared_euclidean_many_f32: squared_euclidean_many_portable,
+        }
+    }
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn detect() -> Dispatch {
+    Dispatch {
+        kernel: Kernel::Portable,
+        squared_euclidean_f64: squared_euclidean_portable,
+        squared_euclidean_f32: squared_euclidean_portable,
+        squared_euclidean_many_f64: squared_euclidean_many_portable,
+        squared_euclidean_many_f32: squared_euclidean_many_portable,
+    }
+}
+
+/// Sum of squared differences between `a` and `b`.
+///
+/// Trailing coordinates of the longer slice are ignored.
+pub(crate) fn squared_euclidean_f64(a: &[f64], b: &[f64]) -> f64 {
+    let len = a.len().min(b.len());
+    // SAFETY: `dispatch` only selects kernels supported by this CPU.
+    unsafe { (dispatch().squared_euclidean_f64)(&a[..len], &b[..len]) }
+}
+
+/// Sum of squared differences between `a` and `b`, accumulated in `f32`.
+///
+/// Trailing coordinates of the longer slice are ignored.
+pub(crate) fn squared_euclidean_f32(a: &[f32], b: &[f32]) -> f32 {
+    let len = a.len().min(b.len());
+    // SAFETY: `dispatch` only selects kernels supported by this CPU.
+    unsafe { (dispatch().squared_euclidean_f32)(&a[..len], &b[..len]) }
+}
+
+/// Sums of squared differences between `query` and each of `targets`.
+///
+/// Trailing coordinates beyond the shortest slice are ignored.
+pub(crate) fn squared_euclidean_many_f64(query: &[f64], targets: [&[f64]; MANY]) -> [f64; MANY] {
+    let len = targets.iter().fold(query.len(), |len, t| len.min(t.len()));
+    // SAFETY: `dispatch` only selects kernels supported by this CPU.
+    unsafe { (dispatch().squared_euclidean_many_f64)(&query[..len], targets.map(|t| &t[..len])) }
+}
+
+/// Sums of squared differences between `query` and each of `targets`, accumulated in `f32`.
+///
+/// Trailing coordinates beyond the shortest slice are ignored.
+pub(crate) fn squared_euclidean_many_f32(query: &[f32], targets: [&[f32]; MANY]) -> [f32; MANY] {
+    let len = targets.iter().fold(query.len(), |len, t| len.min(t.len()));
+    // SAFETY: `dispatch` only selects kernels supported by this CPU.
+    unsafe { (dispatch().squared_euclidean_many_f32)(&query[..len], targets.map(|t| &t[..len])) }
+}
+
+/// Sum of squared differences between `a` and `b`, accumulated in `f64` whatever the element type.
+///
+/// Trailing coordinates of the longer slice are ignored.
+pub(crate) fn squared_euclidean_wide<E: Element>(a: &[E], b: &[E]) -> f64 {
+    let len = a.len().min(b.len());
+    let (a_chunks, a_tail) = a[..len].as_chunks::<LANES>();
+    let (b_chunks, b_tail) = b[..len].as_chunks::<LANES>();
+    let mut acc = [0.0; LANES];
+    for (x, y) in a_chunks.iter().zip(b_chunks) {
+        for lane in 0..LANES {
+            let d = x[lane].to_f64().fsub(y[lane].to_f64());
+            acc[lane] = acc[lane].fadd(d.fmul(d));
+        }
+    }
+    let tail = a_tail.iter().zip(b_tail).fold(0.0, |acc, (x, y)| {
+        let d = x.to_f64().fsub(y.to_f64());
+        acc.fadd(d.fmul(d))
+    });
+    acc.into_iter().fold(tail, Float::fadd)
+}
+
+/// Sum of squared differences between the coordinates yielded by `a` and `b`, for containers that
+/// do not store them contiguously.
+///
+/// Trailing coordinates of the longer point are ignored.
+pub(crate) fn squared_euclidean_iter<'a>(
+    a: impl IntoIterator<Item = &'a f64>,
+    b: impl IntoIterator<Item = &'a f64>,
+) -> f64 {
+    a.into_iter().zip(b).fold(0.0, |acc, (&x, &y)| {
+        let d = x.fsub(y);
+        acc.fadd(d.fmul(d))
+    })
+}
+
+/// Sum of absolute differences between `a` and `b` raised to the power `p`, accumulated in `f64`
+/// whatever the element type.
+///
+/// Trailing coordinates of the longer slice are ignored.
+pub(crate) fn minkowski_wide<E: Element>(a: &[E], b: &[E], p: f64) -> f64 {
+    let len = a.len().min(b.len());
+    let (a_chunks, a_tail) = a[..len].as_chunks::<LANES>();
+    let (b_chunks, b_tail) = b[..len].as_chunks::<LANES>();
+    let mut acc = [0.0; LANES];
+    for (x, y) in a_chunks.iter().zip(b_chunks) {
+        for lane in 0..LANES {
+            let d = x[lane].to_f64().fsub(y[lane].to_f64());
+            acc[lane] = acc[lane].fadd(d.fabs().powf(p));
+        }
+    }
+    let tail = a_tail.iter().zip(b_tail).fold(0.0, |acc, (x, y)| {
+        let d = x.to_f64().fsub(y.to_f64());
+        acc.fadd(d.fabs().powf(p))
+    });
+    acc.into_iter().fold(tail, Float::fadd)
+}
+
+/// Sum of squared differences between `a` and `b` scaled by `weights`, accumulated in `f64`
+/// whatever the element type.
+///
+/// Trailing coordinates of the longer slices are ignored.
+pub(crate) fn weighted_squared_euclidean_wide<E: Element>(
+    a: &[E],
+    b: &[E],
+    weights: &[f64],
+) -> f64 {
+    let len = a.len().min(b.len()).min(weights.len());
+    let (a_chunks, a_tail) = a[..len].as_chunks::<LANES>();
+    let (b_chunks, b_tail) = b[..len].as_chunks::<LANES>();
+    let (w_chunks, w_tail) = weights[..len].as_chunks::<LANES>();
+    let mut acc = [0.0; LANES];
+    for ((x, y), w) in a_chunks.iter().zip(b_chunks).zip(w_chunks) {
+        for lane in 0..LANES {
+            let d = x[lane].to_f64().fsub(y[lane].to_f64());
+            acc[lane] = acc[lane].fadd(w[lane].fmul(d.fmul(d)));
+        }
+    }
+    let tail = a_tail
+        .iter()
+        .zip(b_tail)
+        .zip(w_tail)
+        .fold(0.0, |acc, ((x, y), &w)| {
+            let d = x.to_f64().fsub(y.to_f64());
+            acc.fadd(w.fmul(d.fmul(d)))
+        });
+    acc.into_iter().fold(tail, Float::fadd)
+}
+
+/// Dot product of `a` and `b`, accumulated in `f64` whatever the element type.
+///
+/// Trailing coordinates of the longer slice are ignored.
+pub(crate) fn dot_wide<E: Element>(a: &[E], b: &[E]) -> f64 {
+    let len = a.len().min(b.len());
+    let (a_chunks, a_tail) = a[..len].as_chunks::<LANES>();
+    let (b_chunks, b_tail) = b[..len].as_chunks::<LANES>();
+    let mut acc = [0.0; LANES];
+    for (x, y) in a_chunks.iter().zip(b_chunks) {
+        for lane in 0..LANES {
+            acc[lane] = acc[lane].fadd(x[lane].to_f64().fmul(y[lane].to_f64()));
+        }
+    }
+    let tail = a_tail
+        .iter()
+        .zip(b_tail)
+        .fold(0.0, |acc, (x, y)| acc.fadd(x.to_f64().fmul(y.to_f64())));
+    acc.into_iter().fold(tail, Float::fadd)
+}
+
+/// Coordinate-wise reduction computed by [`reduce`], such as the sum of absolute differences.
+///
+/// This is nominally public as it appears in the sealed element trait, but cannot be named outside
+/// the crate.
+pub trait Reduction {
+    /// Contribution of the difference between two coordinates. Must be non-negative.
+    fn term<F: Float>(d: F) -> F;
+
+    /// Combines two partial results. Must be associative and commutative, with zero as identity.
+    fn combine<F: Float>(a: F, b: F) -> F;
+}
+
+/// Sum of absolute differences raised to the power `P`, for the Minkowski distances.
+///
+/// Powers up to 4 are computed with multiplications alone.
+pub(crate) struct Power<const P: u32>;
+
+impl<const P: u32> Reduction for Power<P> {
+    fn term<F: Float>(d: F) -> F {
+        let d = d.fabs();
+        match P {
+            1 => d,
+            2 => d.fmul(d),
+            3 => d.fmul(d).fmul(d),
+            4 => {
+                let d2 = d.fmul(d);
+                d2.fmul(d2)
+            }
+            _ => d.fpowi(P as i32),
+        }
+    }
+
+    fn combine<F: Float>(a: F, b: F) -> F {
+        a.fadd(b)
+    }
+}
+
+/// Sum of absolute differences, for the Manhattan distance.
+pub(crate) type Manhattan = Power<1>;
+
+/// Largest absolute difference, for the Chebyshev distance.
+pub(crate) struct Chebyshev;
+
+impl Reduction for Chebyshev {
+    fn term<F: Float>(d: F) -> F {
+        d.fabs()
+    }
+
+    fn combine<F: Float>(a: F, b: F) -> F {
+        a.fmax(b)
+    }
+}
+
+/// Reduces the coordinate-wise differences between `a` and `b` with `R`.
+///
+/// On x86-64 the portable kernel is recompiled for the widest instruction set selected by
+/// [`dispatch`]. Trailing coordinates of the longer slice are ignored.
+pub(crate) fn reduce<R: Reduction, F: Float>(a: &[F], b: &[F]) -> F {
+    let len = a.len().min(b.len());
+    let (a, b) = (&a[..len], &b[..len]);
+    #[cfg(target_arch = "x86_64")]
+    // SAFETY: `dispatch` only selects kernels supported by this CPU.
+    unsafe {
+        match dispatch().kernel {
+            Kernel::Avx512 => return x86::reduce_avx512::<R, F>(a, b),
+            Kernel::Avx2Fma => return x86::reduce_avx2_fma::<R, F>(a, b),
+            Kernel::Portable | Kernel::Sse2 => {}
+        }
+    }
+    reduce_portable::<R, F>(a, b)
+}
+
+/// Portable implementation of [`reduce`] for slices of equal length.
+///
+/// This is always inlined so that the x86-64 wrappers can compile it for wider vectors.
+#[inline(always)]
+fn reduce_portable<R: Reduction, F: Float>(a: &[F], b: &[F]) -> F {
+    let (a_chunks, a_tail) = a.as_chunks::<LANES>();
+    let (b_chunks, b_tail) = b.as_chunks::<LANES>();
+    let mut acc = [F::ZERO; LANES];
+    for (x, y) in a_chunks.iter().zip(b_chunks) {
+        for lane in 0..LANES {
+            acc[lane] = R::combine(acc[lane], R::term(x[lane].fsub(y[lane])));
+        }
+    }
+    let tail = a_tail
+        .iter()
+        .zip(b_tail)
+        .fold(F::ZERO, |acc, (&x, &y)| R::combine(acc, R::term(x.fsub(y))));
+    acc.into_iter().fold(tail, R::combine)
+}
+
+/// Portable implementation of the squared Euclidean kernels for slices of equal length.
+#[cfg_attr(target_arch = "x86_64", allow(dead_code))]
+fn squared_euclidean_portable<F: Float>(a: &[F], b: &[F]) -> F {
+    let (a_chunks, a_tail) = a.as_chunks::<LANES>();
+    let (b_chunks, b_tail) = b.as_chunks::<LANES>();
+    F::squared_euclidean_chunks(a_chunks, b_chunks).fadd(squared_euclidean_tail(a_tail, b_tail))
+}
+
+/// Portable implementation of the one-to-many kernels for slices of equal length.
+///
+/// Each chunk of the query is compared against the matching chunk of every target before moving
+/// on, so the query is only read once. This is always inlined so that the x86-64 wrappers can
+/// compile it for wider vectors.
+#[inline(always)]
+fn squared_euclidean_many_portable<F: Float>(query: &[F], targets: [&[F]; MANY]) -> [F; MANY] {
+    let (query_chunks, query_tail) = query.as_chunks::<LANES>();
+    let body = query.len() - query_tail.len();
+    let target_chunks = targets.map(|t| t[..body].as_chunks::<LANES>().0);
+    let mut acc = [[F::ZERO; LANES]; MANY];
+    for (i, x) in query_chunks.iter().enumerate() {
+        for (acc, y) in acc.iter_mut().zip(&target_chunks) {
+            let y = &y[i];
+            for lane in 0..LANES {
+                let d = x[lane].fsub(y[lane]);
+                acc[lane] = acc[lane].fadd(d.fmul(d));
+            }
+        }
+    }
+    let mut out = [F::ZERO; MANY];
+    for ((out, acc), target) in out.iter_mut().zip(acc).zip(targets) {
+        let tail = squared_euclidean_tail(query_tail, &target[body..]);
+        *out = acc.into_iter().fold(tail, F::fadd);
+    }
+    out
+}
+
+fn squared_euclidean_tail<F: Float>(a: &[F], b: &[F]) -> F {
+    a.iter().zip(b).fold(F::ZERO, |acc, (&x, &y)| {
+        let d = x.fsub(y);
+        acc.fadd(d.fmul(d))
+    })
+}
+
+/// Floating-point arithmetic used by the portable kernels.
+///
+/// With the `nightly` feature the operations use the `fast` float intrinsics, and are otherwise
+/// plain IEEE-754 arithmetic.
+pub trait Float: Copy {
+    const ZERO: Self;
+
+    fn fadd(self, rhs: Self) -> Self;
+    fn fsub(self, rhs: Self) -> Self;
+    fn fmul(self, rhs: Self) -> Self;
+    fn fabs(self) -> Self;
+    fn fmax(self, rhs: Self) -> Self;
+    fn fpowi(self, n: i32) -> Self;
+
+    /// Sum of squared differences between chunks of `a` and `b`.
+    fn squared_euclidean_chunks(a: &[[Self; LANES]], b: &[[Self; LANES]]) -> Self;
+}
+
+macro_rules! impl_float {
+    ($($t:ty),*) => {
+        $(
+            impl Float for $t {
+                const ZERO: Self = 0.0;
+
+                #[cfg(feature = "nightly")]
+                fn fadd(self, rhs: Self) -> Self {
+                    unsafe { fadd_fast(self, rhs) }
+                }
+
+                #[cfg(feature = "nightly")]
+                fn fsub(self, rhs: Self) -> Self {
+                    unsafe { fsub_fast(self, rhs) }
+                }
+
+                #[cfg(feature = "nightly")]
+                fn fmul(self, rhs: Self) -> Self {
+                    unsafe { fmul_fast(self, rhs) }
+                }
+
+                #[cfg(not(feature = "nightly"))]
+                fn fadd(self, rhs: Self) -> Self {
+                    self + rhs
+                }
+
+                #[cfg(not(feature = "nightly"))]
+                fn fsub(self, rhs: Self) -> Self {
+                    self - rhs
+                }
+
+                #[cfg(not(feature = "nightly"))]
+                fn fmul(self, rhs: Self) -> Self {
+                    self * rhs
+                }
+
+                fn fabs(self) -> Self {
+                    self.abs()
+                }
+
+                fn fpowi(self, n: i32) -> Self {
+                    self.powi(n)
+                }
+
+                // Unlike `max`, this ignores NaN, so it compiles to a single instruction.
+                fn fmax(self, rhs: Self) -> Self {
+                    if self > rhs {
+                        self
+                    } else {
+                        rhs
+                    }
+                }
+
+                #[cfg(feature = "nightly")]
+                fn squared_euclidean_chunks(a: &[[Self; LANES]], b: &[[Self; LANES]]) -> Self {
+                    let mut acc = Simd::<Self, LANES>::splat(0.0);
+                    for (x, y) in a.iter().zip(b) {
+                        let d = Simd::from_array(*x) - Simd::from_array(*y);
+                        acc += d * d;
+                    }
+                    acc.reduce_sum()
+                }
+
+                #[cfg(not(feature = "nightly"))]
+                fn squared_euclidean_chunks(a: &[[Self; LANES]], b: &[[Self; LANES]]) -> Self {
+                    let mut acc = [0.0; LANES];
+                    for (x, y) in a.iter().zip(b) {
+                        for lane in 0..LANES {
+                            let d = x[lane] - y[lane];
+                            acc[lane] += d * d;
+                        }
+                    }
+                    acc.iter().sum()
+                }
+            }
+        )*
+    };
+}
+
+impl_float!(f32, f64);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Lengths on both sides of every chunk boundary of the portable and x86-64 kernels.
+    const LENGTHS: [usize; 22] = [
+        0, 1, 2, 3, 4, 5, 7, 8, 9, 15, 16, 17, 31, 32, 33, 47, 48, 49, 63, 64, 65, 100,
+    ];
+
+    type KernelFn<F> = unsafe fn(&[F], &[F]) -> F;
+    type ManyKernel<F> = unsafe fn(&[F], [&[F]; MANY]) -> [F; MANY];
+
+    /// A query and targets with small integer coordinates, so that every reduction is exact
+    /// whatever order the kernel sums in.
+    fn points<F: From<i16>>(len: usize) -> (Vec<F>, [Vec<F>; MANY]) {
+        let point = |seed: usize| {
+            (0..len)
+                .map(|i| F::from(((i * 7 + seed * 13) % 11) as i16 - 5))
+                .collect()
+        };
+        (point(0), [point(1), point(2), point(3), point(4)])
+    }
+
+    fn expected<F: Into<f64> + Copy>(
+        a: &[F],
+        b: &[F],
+        term: fn(f64) -> f64,
+        combine: fn(f64, f64) -> f64,
+    ) -> f64 {
+        a.iter()
+            .zip(b)
+            .fold(0.0, |acc, (&x, &y)| combine(acc, term(x.into() - y.into())))
+    }
+
+    fn check<F>(kernel: KernelFn<F>, many: ManyKernel<F>)
+    where
+        F: Float + From<i16> + Into<f64>,
+    {
+        for len in LENGTHS {
+            let (query, targets) = points::<F>(len);
+            let squares = targets
+                .each_ref()
+                .map(|t| expected(&query, t, |d| d * d, |a, b| a + b));
+            // SAFETY: callers only pass kernels supported by this CPU.
+            let (single, batch) = unsafe {
+                (
+                    targets.each_ref().map(|t| kernel(&query, t).into()),
+                    many(&query, targets.each_ref().map(Vec::as_slice)).map(Into::into),
+                )
+            };
+            assert_eq!(single, squares, "single, length {len}");
+            assert_eq!(batch, squares, "many, length {len}");
+        }
+    }
+
+    fn check_reduce<F>(manhattan: KernelFn<F>, chebyshev: KernelFn<F>)
+    where
+        F: Float + From<i16> + Into<f64>,
+    {
+        for len in LENGTHS {
+            let (a, [b, ..]) = points::<F>(len);
+            // SAFETY: callers only pass kernels supported by this CPU.
+            let (sum, max) = unsafe { (manhattan(&a, &b).into(), chebyshev(&a, &b).into()) };
+            assert_eq!(
+                sum,
+                expected(&a, &b, f64::abs, |a, b| a + b),
+                "length {len}"
+            );
+            assert_eq!(max, expected(&a, &b, f64::abs, f64::max), "length {len}");
+        }
+    }
+
+    #[test]
+    fn portable() {
+        check::<f64>(squared_euclidean_portable, squared_euclidean_many_portable);
+        check::<f32>(squared_euclidean_portable, squared_euclidean_many_portable);
+        check_reduce::<f64>(
+            reduce_portable::<Manhattan, _>,
+            reduce_portable::<Chebyshev, _>,
+        );
+        check_reduce::<f32>(
+            reduce_portable::<Manhattan, _>,
+            reduce_portable::<Chebyshev, _>,
+        );
+    }
+
+    #[test]
+    #[cfg(target_arch = "x86_64")]
+    fn sse2() {
+        if !is_x86_feature_detected!("sse2") {
+            return;
+        }
+        check::<f64>(
+            x86::squared_euclidean_f64_sse2,
+            squared_euclidean_many_portable,
+        );
+        check::<f32>(
+            x86::squared_euclidean_f32_sse2,
+            squared_euclidean_many_portable,
+        );
+    }
+
+    #[test]
+    #[cfg(target_arch = "x86_64")]
+    fn avx2_fma() {
+        if !(is_x86_feature_detected!("avx2") && is_x86_feature_detected!("fma")) {
+            return;
+        }
+        check::<f64>(
+            x86::squared_euclidean_f64_avx2_fma,
+            x86::squared_euclidean_many_f64_avx2_fma,
+        );
+        check::<f32>(
+            x86::squared_euclidean_f32_avx2_fma,
+            x86::squared_euclidean_many_f32_avx2_fma,
+        );
+        check_reduce::<f64>(
+            x86::reduce_avx2_fma::<Manhattan, _>,
+            x86::reduce_avx2_fma::<Chebyshev, _>,
+        );
+        check_reduce::<f32>(
+            x86::reduce_avx2_fma::<Manhattan, _>,
+            x86::reduce_avx2_fma::<Chebyshev, _>,
+        );
+    }
+
+    #[test]
+    #[cfg(target_arch = "x86_64")]
+    fn avx512() {
+        if !is_x86_feature_detected!("avx512f") {
+            return;
+        }
+        check::<f64>(
+            x86::squared_euclidean_f64_avx512,
+            x86::squared_euclidean_many_f64_avx512,
+        );
+        check::<f32>(
+            x86::squared_euclidean_f32_avx512,
+            x86::squared_euclidean_many_f32_avx512,
+        );
+        check_reduce::<f64>(
+            x86::reduce_avx512::<Manhattan, _>,
+            x86::reduce_avx512::<Chebyshev, _>,
+        );
+        check_reduce::<f32>(
+            x86::reduce_avx512::<Manhattan, _>,
+            x86::reduce_avx512::<Chebyshev, _>,
+        );
+    }
+}
@@ -0,0 +1,178 @@
+//! Explicit x86-64 kernels, selected at runtime by [`detect`](super::detect).
+//!
+//! Every kernel expects slices of equal length, and finishes the coordinates left over after the
+//! last full vector with the portable scalar tail. The one-to-many kernels and the generic
+//! reductions are the portable ones, recompiled with the wider instruction sets enabled.
+
+use std::arch::x86_64::*;
+
+use super::{
+    reduce_portable, squared_euclidean_many_portable, squared_euclidean_tail, Float, Reduction,
+    MANY,
+};
+
+/// # Safety
+/// The CPU must support SSE2.
+#[target_feature(enable = "sse2")]
+pub(super) unsafe fn squared_euclidean_f64_sse2(a: &[f64], b: &[f64]) -> f64 {
+    const STEP: usize = 4;
+    let body = a.len() - a.len() % STEP;
+    let (mut acc0, mut acc1) = (_mm_setzero_pd(), _mm_setzero_pd());
+    for i in (0..body).step_by(STEP) {
+        let (x, y) = (a.as_ptr().add(i), b.as_ptr().add(i));
+        let d0 = _mm_sub_pd(_mm_loadu_pd(x), _mm_loadu_pd(y));
+        let d1 = _mm_sub_pd(_mm_loadu_pd(x.add(2)), _mm_loadu_pd(y.add(2)));
+        acc0 = _mm_add_pd(acc0, _mm_mul_pd(d0, d0));
+        acc1 = _mm_add_pd(acc1, _mm_mul_pd(d1, d1));
+    }
+    let mut lanes = [0.0; 2];
+    _mm_storeu_pd(lanes.as_mut_ptr(), _mm_add_pd(acc0, acc1));
+    lanes.iter().sum::<f64>() + squared_euclidean_tail(&a[body..], &b[body..])
+}
+
+/// # Safety
+/// The CPU must support SSE2.
+#[target_feature(enable = "sse2")]
+pub(super) unsafe fn squared_euclidean_f32_sse2(a: &[f32], b: &[f32]) -> f32 {
+    const STEP: usize = 8;
+    let body = a.len() - a.len() % STEP;
+    let (mut acc0, mut acc1) = (_mm_setzero_ps(), _mm_setzero_ps());
+    for i in (0..body).step_by(STEP) {
+        let (x, y) = (a.as_ptr().add(i), b.as_ptr().add(i));
+        let d0 = _mm_sub_ps(_mm_loadu_ps(x), _mm_loadu_ps(y));
+        let d1 = _mm_sub_ps(_mm_loadu_ps(x.add(4)), _mm_loadu_ps(y.add(4)));
+        acc0 = _mm_add_ps(acc0, _mm_mul_ps(d0, d0));
+        acc1 = _mm_add_ps(acc1, _mm_mul_ps(d1, d1));
+    }
+    let mut lanes = [0.0; 4];
+    _mm_storeu_ps(lanes.as_mut_ptr(), _mm_add_ps(acc0, acc1));
+    lanes.iter().sum::<f32>() + squared_euclidean_tail(&a[body..], &b[body..])
+}
+
+/// # Safety
+/// The CPU must support AVX2 and FMA.
+#[target_feature(enable = "avx2,fma")]
+pub(super) unsafe fn squared_euclidean_f64_avx2_fma(a: &[f64], b: &[f64]) -> f64 {
+    const STEP: usize = 16;
+    let body = a.len() - a.len() % STEP;
+    let mut acc = [_mm256_setzero_pd(); 4];
+    for i in (0..body).step_by(STEP) {
+        let (x, y) = (a.as_ptr().add(i), b.as_ptr().add(i));
+        for (j, acc) in acc.iter_mut().enumerate() {
+            let d = _mm256_sub_pd(_mm256_loadu_pd(x.add(4 * j)), _mm256_loadu_pd(y.add(4 * j)));
+            *acc = _mm256_fmadd_pd(d, d, *acc);
+        }
+    }
+    let sum = _mm256_add_pd(_mm256_add_pd(acc[0], acc[1]), _mm256_add_pd(acc[2], acc[3]));
+    let mut lanes = [0.0; 4];
+    _mm256_storeu_pd(lanes.as_mut_ptr(), sum);
+    lanes.iter().sum::<f64>() + squared_euclidean_tail(&a[body..], &b[body..])
+}
+
+/// # Safety
+/// The CPU must support AVX2 and FMA.
+#[target_feature(enable = "avx2,fma")]
+pub(super) unsafe fn squared_euclidean_f32_avx2_fma(a: &[f32], b: &[f32]) -> f32 {
+    const STEP: usize = 32;
+    let body = a.len() - a.len() % STEP;
+    let mut acc = [_mm256_setzero_ps(); 4];
+    for i in (0..body).step_by(STEP) {
+        let (x, y) = (a.as_ptr().add(i), b.as_ptr().add(i));
+        for (j, acc) in acc.iter_mut().enumerate() {
+            let d = _mm256_sub_ps(_mm256_loadu_ps(x.add(8 * j)), _mm256_loadu_ps(y.add(8 * j)));
+            *acc = _mm256_fmadd_ps(d, d, *acc);
+        }
+    }
+    let sum = _mm256_add_ps(_mm256_add_ps(acc[0], acc[1]), _mm256_add_ps(acc[2], acc[3]));
+    let mut lanes = [0.0; 8];
+    _mm256_storeu_ps(lanes.as_mut_ptr(), sum);
+    lanes.iter().sum::<f32>() + squared_euclidean_tail(&a[body..], &b[body..])
+}
+
+/// # Safety
+/// The CPU must support AVX-512F.
+#[target_feature(enable = "avx512f")]
+pub(super) unsafe fn squared_euclidean_f64_avx512(a: &[f64], b: &[f64]) -> f64 {
+    const STEP: usize = 16;
+    let body = a.len() - a.len() % STEP;
+    let (mut acc0, mut acc1) = (_mm512_setzero_pd(), _mm512_setzero_pd());
+    for i in (0..body).step_by(STEP) {
+        let (x, y) = (a.as_ptr().add(i), b.as_ptr().add(i));
+        let d0 = _mm512_sub_pd(_mm512_loadu_pd(x), _mm512_loadu_pd(y));
+        let d1 = _mm512_sub_pd(_mm512_loadu_pd(x.add(8)), _mm512_loadu_pd(y.add(8)));
+        acc0 = _mm512_fmadd_pd(d0, d0, acc0);
+        acc1 = _mm512_fmadd_pd(d1, d1, acc1);
+    }
+    _mm512_reduce_add_pd(_mm512_add_pd(acc0, acc1)) + squared_euclidean_tail(&a[body..], &b[body..])
+}
+
+/// # Safety
+/// The CPU must support AVX-512F.
+#[target_feature(enable = "avx512f")]
+pub(super) unsafe fn squared_euclidean_f32_avx512(a: &[f32], b: &[f32]) -> f32 {
+    const STEP: usize = 32;
+    let body = a.len() - a.len() % STEP;
+    let (mut acc0, mut acc1) = (_mm512_setzero_ps(), _mm512_setzero_ps());
+    for i in (0..body).step_by(STEP) {
+        let (x, y) = (a.as_ptr().add(i), b.as_ptr().add(i));
+        let d0 = _mm512_sub_ps(_mm512_loadu_ps(x), _mm512_loadu_ps(y));
+        let d1 = _mm512_sub_ps(_mm512_loadu_ps(x.add(16)), _mm512_loadu_ps(y.add(16)));
+        acc0 = _mm512_fmadd_ps(d0, d0, acc0);
+        acc1 = _mm512_fmadd_ps(d1, d1, acc1);
+    }
+    _mm512_reduce_add_ps(_mm512_add_ps(acc0, acc1)) + squared_euclidean_tail(&a[body..], &b[body..])
+}
+
+/// # Safety
+/// The CPU must support AVX2 and FMA.
+#[target_feature(enable = "avx2,fma")]
+pub(super) unsafe fn squared_euclidean_many_f64_avx2_fma(
+    query: &[f64],
+    targets: [&[f64]; MANY],
+) -> [f64; MANY] {
+    squared_euclidean_many_portable(query, targets)
+}
+
+/// # Safety
+/// The CPU must support AVX2 and FMA.
+#[target_feature(enable = "avx2,fma")]
+pub(super) unsafe fn squared_euclidean_many_f32_avx2_fma(
+    query: &[f32],
+    targets: [&[f32]; MANY],
+) -> [f32; MANY] {
+    squared_euclidean_many_portable(query, targets)
+}
+
+/// # Safety
+/// The CPU must support AVX-512F.
+#[target_feature(enable = "avx512f")]
+pub(super) unsafe fn squared_euclidean_many_f64_avx512(
+    query: &[f64],
+    targets: [&[f64]; MANY],
+) -> [f64; MANY] {
+    squared_euclidean_many_portable(query, targets)
+}
+
+/// # Safety
+/// The CPU must support AVX-512F.
+#[target_feature(enable = "avx512f")]
+pub(super) unsafe fn squared_euclidean_many_f32_avx512(
+    query: &[f32],
+    targets: [&[f32]; MANY],
+) -> [f32; MANY] {
+    squared_euclidean_many_portable(query, targets)
+}
+
+/// # Safety
+/// The CPU must support AVX2 and FMA.
+#[target_feature(enable = "avx2,fma")]
+pub(super) unsafe fn reduce_avx2_fma<R: Reduction, F: Float>(a: &[F], b: &[F]) -> F {
+    reduce_portable::<R, F>(a, b)
+}
+
+/// # Safety
+/// The CPU must support AVX-512F.
+#[target_feature(enable = "avx512f")]
+pub(super) unsafe fn reduce_avx512<R: Reduction, F: Float>(a: &[F], b: &[F]) -> F {
+    reduce_portable::<R, F>(a, b)
+}
@@ -1,11 +1,57 @@
-#![feature(core_intrinsics)]
+#![cfg_attr(feature = "nightly", feature(core_intrinsics, portable_simd))]
+#![cfg_attr(feature = "nightly", allow(internal_features))]
 
 use bitpart::metric::Metric;
-use std::intrinsics::{fadd_fast, fmul_fast, fsub_fast};
-use std::ops::Deref;
+use element::private::Sealed;
 
+#[macro_use]
+mod macros;
+
+mod batch;
+mod chebyshev;
+mod coordinates;
+pub mod csv;
+mod dataset;
+mod element;
+mod error;
+mod iter;
+mod kernel;
+mod mahalanobis;
+mod manhattan;
+mod minkowski;
+#[cfg(feature = "mmap")]
+pub mod mmap;
+mod norm_cached;
+pub mod npy;
+mod pairwise;
+pub mod threshold;
+#[cfg(feature = "serde")]
+pub mod unchecked;
+pub mod vecs;
+mod weighted;
+
+pub use batch::DistancesToMany;
+pub use chebyshev::FastChebyshev;
+pub use coordinates::Coordinates;
+#[cfg(feature = "rkyv")]
+pub use dataset::ArchivedFastEuclideanDataset;
+pub use dataset::{FastEuclideanDataset, FastEuclideanDatasetView, Points};
+#[cfg(feature = "rkyv")]
+pub use element::ArchiveElement;
+pub use element::Element;
+pub use error::{
+    DatasetError, DimensionMismatch, FileError, PointMismatch, ValidationError, WhiteningError,
+};
+pub use iter::FastEuclideanIter;
+pub use kernel::{active_kernel, Kernel};
+pub use mahalanobis::Whitening;
+pub use manhattan::FastManhattan;
+pub use minkowski::{FastMinkowski, FastMinkowskiDyn};
+pub use norm_cached::NormCachedEuclidean;
+pub use pairwise::pairwise_distances;
 #[cfg(feature = "serde")]
-use serde::{Deserialize, Serialize};
+pub use weighted::WeightedSeed;
+pub use weighted::{FastWeightedEuclidean, Weights};
 
 /// Wrapper struct to apply Euclidean distance to an object set.
 ///
@@ -22,89 +68,254 @@ use serde::{Deserialize, Serialize};
 #[derive(Debug, Clone)]
 pub struct FastEuclidean<T>(T);
 
+/// A `FastEuclidean` point borrowing its coordinates from a buffer it does not own.
+///
+/// Any `&C` where `C: Coordinates` is itself `Coordinates`, and shared references are always
+/// `Clone`, so views satisfy the bounds of the [`Metric`] impl without copying. This allows
+/// indexing memory-mapped or externally owned buffers directly, as long as the buffer outlives the
+/// index.
+/// # Example
+/// ```
+/// # use bitpart::metric::Metric;
+/// # use bitpart_fast_euclidean::FastEuclideanView;
+/// let buffer: Vec<f32> = vec![0.0, 0.0, 3.0, 4.0];
+/// let points: Vec<FastEuclideanView<f32>> = buffer
+///     .chunks_exact(2)
+///     .map(|p| FastEuclideanView::try_new(p).unwrap())
+///     .collect();
+///
+/// assert_eq!(points[0].distance(&points[1]), 5.0);
+/// ```
+pub type FastEuclideanView<'a, E = f64> = FastEuclidean<&'a [E]>;
+
 impl<T> FastEuclidean<T> {
     /// Creates a new `FastEuclidean`.
     /// # Safety
     /// You **must** ensure that the euclidean distance measurements between
     /// any two points cannot return [`NaN`](f64::NAN) nor [`INF`](f64::INFINITY).
+    /// For `f32` coordinates, the distance must also fit in an `f32`.
     pub unsafe fn new(t: T) -> Self {
         Self(t)
     }
 }
 
-impl<T> Deref for FastEuclidean<T> {
-    type Target = T;
+impl<T> FastEuclidean<T>
+where
+    T: Coordinates,
+{
+    /// Creates a new `FastEuclidean`, checking that the safety requirements of [`new`](Self::new)
+    /// are upheld.
+    ///
+    /// Every coordinate must be finite, and small enough in magnitude that the squared distance to
+    /// any other point of the same dimension passing this check cannot overflow.
+    /// # Example
+    /// ```
+    /// # use bitpart_fast_euclidean::{FastEuclidean, ValidationError};
+    /// assert!(FastEuclidean::<[f64; 2]>::try_new([0.0, 1.0]).is_ok());
+    /// assert!(matches!(
+    ///     FastEuclidean::<[f64; 2]>::try_new([0.0, f64::NAN]),
+    ///     Err(ValidationError::NonFinite { index: 1, .. })
+    /// ));
+    /// ```
+    pub fn try_new(t: T) -> Result<Self, ValidationError> {
+        validate(t.coordinates())?;
+        Ok(Self(t))
+    }
 
-    fn deref(&self) -> &Self::Target {
-        &self.0
+    /// Borrows the point as a [`FastEuclideanView`].
+    pub fn as_view(&self) -> FastEuclideanView<'_, T::Element> {
+        FastEuclidean(self.0.coordinates())
+    }
+
+    /// Copies the coordinates of the point into an owned `Vec`.
+    pub fn to_vec(&self) -> FastEuclidean<Vec<T::Element>> {
+        FastEuclidean(self.0.coordinates().to_vec())
+    }
+
+    /// Calculates the distance to `rhs`, accumulating in `f64` whatever the element type.
+    ///
+    /// For `f32` points this trades some speed for the precision of [`distance`](Metric::distance)
+    /// on `f64` points. For `f64` points the two are equivalent.
+    /// # Example
+    /// ```
+    /// # use bitpart_fast_euclidean::FastEuclidean;
+    /// let point1 = FastEuclidean::<[f32; 2]>::try_new([0.0, 0.0]).unwrap();
+    /// let point2 = FastEuclidean::<[f32; 2]>::try_new([1.0, 1.0]).unwrap();
+    ///
+    /// assert_eq!(point1.distance_wide(&point2), 2.0_f64.sqrt());
+    /// ```
+    pub fn distance_wide(&self, rhs: &Self) -> f64 {
+        let (lhs, rhs) = (self.0.coordinates(), rhs.0.coordinates());
+        debug_assert_eq!(lhs.len(), rhs.len(), "points have different dimensions");
+        kernel::squared_euclidean_wide(lhs, rhs).sqrt()
+    }
+
+    /// Calculates the squared distance to `rhs`, skipping the final square root.
+    ///
+    /// Squared distances preserve ordering, so they can be compared against a threshold squared
+    /// ahead of time with [`threshold::to_squared`]. They are **not** a metric however, and must
+    /// not be used in place of [`distance`](Metric::distance) inside bitpart.
+    /// # Example
+    /// ```
+    /// # use bitpart_fast_euclidean::{threshold, FastEuclidean};
+    /// let point1 = FastEuclidean::<[f64; 2]>::try_new([0.0, 0.0]).unwrap();
+    /// let point2 = FastEuclidean::<[f64; 2]>::try_new([3.0, 4.0]).unwrap();
+    ///
+    /// assert_eq!(point1.squared_distance(&point2), 25.0);
+    /// assert!(point1.squared_distance(&point2) <= threshold::to_squared(5.0));
+    /// ```
+    pub fn squared_distance(&self, rhs: &Self) -> f64 {
+        let (lhs, rhs) = (self.0.coordinates(), rhs.0.coordinates());
+        debug_assert_eq!(lhs.len(), rhs.len(), "points have different dimensions");
+        T::Element::squared_euclidean(lhs, rhs)
+    }
+
+    /// Calculates the distance to `rhs` if it does not exceed `limit`.
+    ///
+    /// The squared distance is accumulated a block of coordinates at a time, and the calculation
+    /// is abandoned as soon as the partial sum exceeds `limit²`. Within each block the usual
+    /// kernels are used, so points that are not rejected cost little more than
+    /// [`distance`](Metric::distance). A negative or [`NaN`](f64::NAN) limit rejects every point.
+    /// # Example
+    /// ```
+    /// # use bitpart_fast_euclidean::FastEuclidean;
+    /// let point1 = FastEuclidean::<[f64; 2]>::try_new([0.0, 0.0]).unwrap();
+    /// let point2 = FastEuclidean::<[f64; 2]>::try_new([3.0, 4.0]).unwrap();
+    ///
+    /// assert_eq!(point1.distance_bounded(&point2, 5.0), Some(5.0));
+    /// assert_eq!(point1.distance_bounded(&point2, 4.0), None);
+    /// assert_eq!(point1.distance_bounded(&point2, f64::NAN), None);
+    /// ```
+    pub fn distance_bounded(&self, rhs: &Self, limit: f64) -> Option<f64> {
+        let (lhs, rhs) = (self.0.coordinates(), rhs.0.coordinates());
+        debug_assert_eq!(lhs.len(), rhs.len(), "points have different dimensions");
+        if limit.is_nan() || limit < 0.0 {
+            return None;
+        }
+        let bound = limit * limit;
+        let mut sum = 0.0;
+        for (x, y) in lhs.chunks(BOUNDED_BLOCK).zip(rhs.chunks(BOUNDED_BLOCK)) {
+            sum += T::Element::squared_euclidean(x, y);
+            if sum > bound {
+                return None;
+            }
+        }
+        Some(sum.sqrt())
     }
 }
 
-impl<T> IntoIterator for FastEuclidean<T>
-where
-    T: IntoIterator,
-{
-    type Item = <T as IntoIterator>::Item;
-    type IntoIter = <T as IntoIterator>::IntoIter;
+/// Number of coordinates reduced by the `distance_bounded` methods between checks against the
+/// limit.
+const BOUNDED_BLOCK: usize = 64;
+
+/// Checks every coordinate against the [`FastEuclidean::new`] safety contract.
+fn validate<E: Element>(coordinates: &[E]) -> Result<(), ValidationError> {
+    validate_within(coordinates, coordinate_limit::<E>(coordinates.len()))
+}
+
+/// Checks that every coordinate is finite, with a magnitude of at most `limit`.
+fn validate_within<E: Element>(coordinates: &[E], limit: f64) -> Result<(), ValidationError> {
+    coordinates
+        .iter()
+        .enumerate()
+        .try_for_each(|(index, value)| check_coordinate(index, value.to_f64(), limit))
+}
 
-    fn into_iter(self) -> Self::IntoIter {
-        self.0.into_iter()
+/// Checks that the coordinate at `index` is finite, with a magnitude of at most `limit`.
+fn check_coordinate(index: usize, value: f64, limit: f64) -> Result<(), ValidationError> {
+    if !value.is_finite() {
+        Err(ValidationError::NonFinite { index, value })
+    } else if value.abs() > limit {
+        Err(ValidationError::OutOfRange {
+            index,
+            value,
+            limit,
+        })
+    } else {
+        Ok(())
     }
 }
 
-impl<'a, T> IntoIterator for &'a FastEuclidean<T>
-where
-    &'a T: IntoIterator,
-{
-    type Item = <&'a T as IntoIterator>::Item;
-    type IntoIter = <&'a T as IntoIterator>::IntoIter;
+/// Largest coordinate magnitude accepted for a point of the given dimension.
+fn coordinate_limit<E: Element>(dimension: usize) -> f64 {
+    limit_for_sum(E::ACCUMULATOR_MAX, dimension, 2.0, 1.0)
+}
 
-    fn into_iter(self) -> Self::IntoIter {
-        self.0.into_iter()
+/// Largest coordinate magnitude accepted for a metric summing `dimension` terms of
+/// `scale * |xᵢ - yᵢ|^exponent` in an accumulator whose largest finite value is `accumulator`.
+///
+/// Two points within this bound differ by at most `2 * limit` per coordinate, so the sum is at
+/// most half the accumulator's range, leaving headroom for rounding in the reordered sum.
+fn limit_for_sum(accumulator: f64, dimension: usize, exponent: f64, scale: f64) -> f64 {
+    (accumulator / (2.0 * scale * dimension as f64)).powf(exponent.recip()) / 2.0
+}
+
+/// Checks that two points have the same dimension, before comparing them with a metric that
+/// would silently ignore the trailing coordinates of the longer one.
+fn check_dimensions<E>(lhs: &[E], rhs: &[E]) -> Result<(), DimensionMismatch> {
+    let (left, right) = (lhs.len(), rhs.len());
+    if left != right {
+        return Err(DimensionMismatch { left, right });
     }
+    Ok(())
 }
 
+impl_wrapper!(FastEuclidean);
+
+/// `T` only needs to expose its coordinates through [`Coordinates`], so owned containers, smart
+/// pointers and borrowed slices (see [`FastEuclideanView`]) all qualify. `Clone` is required by
+/// [`Metric`] itself.
+///
+/// This bound replaces the earlier `for<'a> &'a T: IntoIterator<Item = &'a f64>`, which breaks
+/// points stored in containers without contiguous coordinates, such as
+/// [`VecDeque`](std::collections::VecDeque). Wrap those in [`FastEuclideanIter`] instead.
 impl<T> Metric for FastEuclidean<T>
 where
-    for<'a> &'a T: IntoIterator<Item = &'a f64>,
-    T: Clone,
+    T: Coordinates + Clone,
 {
     fn distance(&self, rhs: &FastEuclidean<T>) -> f64 {
-        self.0
-            .into_iter()
-            .zip(rhs.0.into_iter())
-            .map(|(&x, &y)| unsafe {
-                let a = fsub_fast(x, y);
-                fmul_fast(a, a)
-            })
-            .fold(0.0, |acc, v| unsafe { fadd_fast(acc, v) })
-            .sqrt()
+        self.squared_distance(rhs).sqrt()
     }
 }
 
-#[cfg(feature = "serde")]
-impl<T> Serialize for FastEuclidean<T>
-where
-    T: Serialize,
-{
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: serde::Serializer,
-    {
-        self.0.serialize(serializer)
+#[cfg(all(test, feature = "serde"))]
+mod serde_tests {
+    use super::*;
+
+    #[test]
+    fn json_rejects_out_of_range() {
+        let valid: FastEuclidean<[f64; 2]> = serde_json::from_str("[0.0, 1.0]").unwrap();
+        assert_eq!(*valid, [0.0, 1.0]);
+
+        let error = serde_json::from_str::<FastEuclidean<[f64; 2]>>("[0.0, 1e300]").unwrap_err();
+        assert!(error.to_string().contains("coordinate 1 is out of range"));
     }
-}
 
-#[cfg(feature = "serde")]
-impl<'de, T> Deserialize<'de> for FastEuclidean<T>
-where
-    T: Deserialize<'de>,
-{
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: serde::Deserializer<'de>,
-    {
-        unsafe { Ok(FastEuclidean::new(T::deserialize(deserializer)?)) }
+    #[test]
+    fn bincode_rejects_invalid() {
+        let valid = bincode::serialize(&[0.0_f64, 1.0]).unwrap();
+        let point: FastEuclidean<[f64; 2]> = bincode::deserialize(&valid).unwrap();
+        assert_eq!(*point, [0.0, 1.0]);
+
+        for (value, message) in [
+            (f64::NAN, "coordinate 1 is not finite"),
+            (f64::INFINITY, "coordinate 1 is not finite"),
+            (1e300, "coordinate 1 is out of range"),
+        ] {
+            let bytes = bincode::serialize(&vec![0.0_f64, value]).unwrap();
+            let error = bincode::deserialize::<FastEuclidean<Vec<f64>>>(&bytes).unwrap_err();
+            assert!(error.to_string().contains(message), "{error}");
+        }
+    }
+
+    #[test]
+    fn unchecked_skips_validation() {
+        let bytes = bincode::serialize(&[0.0_f64, 1e300]).unwrap();
+        let mut deserializer =
+            bincode::Deserializer::from_slice(&bytes, bincode::DefaultOptions::new());
+        let point: FastEuclidean<[f64; 2]> =
+            unsafe { unchecked::deserialize(&mut deserializer) }.unwrap();
+        assert_eq!(*point, [0.0, 1e300]);
     }
 }
 
@@ -0,0 +1,125 @@
+/// Implements the traits and methods shared by every fast metric wrapper.
+///
+/// The wrapper must be a tuple struct around its point, with inherent `unsafe fn new` and
+/// `fn try_new` constructors and a [`Metric`](bitpart::metric::Metric) impl, and may take one
+/// extra const generic parameter. The generated impls cover the array dimension constant,
+/// `try_distance`, `TryFrom` for the common containers, `Deref`, `IntoIterator`, and the serde
+/// impls, which validate through `try_new` unless the caller opts out through
+/// [`unchecked`](crate::unchecked).
+macro_rules! impl_wrapper {
+    ($name:ident $(, const $c:ident: $ct:ty)?) => {
+        impl<E, const N: usize $(, const $c: $ct)?> $name<[E; N] $(, $c)?> {
+            /// Dimension of every point of this type.
+            ///
+            /// Since both operands of [`distance`](bitpart::metric::Metric::distance) share the
+            /// same `N`, array-backed points can never be compared across dimensions.
+            pub const DIMENSION: usize = N;
+        }
+
+        impl<T $(, const $c: $ct)?> $name<T $(, $c)?>
+        where
+            T: $crate::Coordinates + Clone,
+        {
+            /// Calculates the distance to `rhs`, first checking that both points have the same
+            /// dimension.
+            ///
+            /// [`distance`](bitpart::metric::Metric::distance) silently ignores the trailing
+            /// coordinates of the longer point, and only asserts on a mismatch in debug builds.
+            pub fn try_distance(&self, rhs: &Self) -> Result<f64, $crate::DimensionMismatch> {
+                $crate::check_dimensions(
+                    $crate::Coordinates::coordinates(&self.0),
+                    $crate::Coordinates::coordinates(&rhs.0),
+                )?;
+                Ok(::bitpart::metric::Metric::distance(self, rhs))
+            }
+        }
+
+        impl_wrapper!(@try_from $name [$(const $c: $ct)?] [$($c)?] [E; N], [E: $crate::Element, const N: usize]);
+        impl_wrapper!(@try_from $name [$(const $c: $ct)?] [$($c)?] Vec<E>, [E: $crate::Element]);
+        impl_wrapper!(@try_from $name [$(const $c: $ct)?] [$($c)?] Box<[E]>, [E: $crate::Element]);
+        impl_wrapper!(@try_from $name [$(const $c: $ct)?] [$($c)?] &'a [E], ['a, E: $crate::Element]);
+
+        impl<T $(, const $c: $ct)?> ::std::ops::Deref for $name<T $(, $c)?> {
+            type Target = T;
+
+            fn deref(&self) -> &Self::Target {
+                &self.0
+            }
+        }
+
+        impl<T $(, const $c: $ct)?> IntoIterator for $name<T $(, $c)?>
+        where
+            T: IntoIterator,
+        {
+            type Item = <T as IntoIterator>::Item;
+            type IntoIter = <T as IntoIterator>::IntoIter;
+
+            fn into_iter(self) -> Self::IntoIter {
+                self.0.into_iter()
+            }
+        }
+
+        impl<'a, T $(, const $c: $ct)?> IntoIterator for &'a $name<T $(, $c)?>
+        where
+            &'a T: IntoIterator,
+        {
+            type Item = <&'a T as IntoIterator>::Item;
+            type IntoIter = <&'a T as IntoIterator>::IntoIter;
+
+            fn into_iter(self) -> Self::IntoIter {
+                self.0.into_iter()
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl<T $(, const $c: $ct)?> ::serde::Serialize for $name<T $(, $c)?>
+        where
+            T: ::serde::Serialize,
+        {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: ::serde::Serializer,
+            {
+                self.0.serialize(serializer)
+            }
+        }
+
+        /// Points are validated as in `try_new`, failing with a serde error if they do not uphold
+        /// the safety contract. Trusted inputs can skip validation with
+        /// [`unchecked::deserialize`](crate::unchecked::deserialize).
+        #[cfg(feature = "serde")]
+        impl<'de, T $(, const $c: $ct)?> ::serde::Deserialize<'de> for $name<T $(, $c)?>
+        where
+            T: ::serde::Deserialize<'de> + $crate::Coordinates,
+        {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: ::serde::Deserializer<'de>,
+            {
+                $name::try_new(T::deserialize(deserializer)?).map_err(::serde::de::Error::custom)
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl<'de, T $(, const $c: $ct)?> $crate::unchecked::Unchecked<'de> for $name<T $(, $c)?>
+        where
+            T: ::serde::Deserialize<'de>,
+        {
+            unsafe fn deserialize_unchecked<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: ::serde::Deserializer<'de>,
+            {
+                Ok(Self::new(T::deserialize(deserializer)?))
+            }
+        }
+    };
+    (@try_from $name:ident [$($cg:tt)*] [$($c:ident)?] $t:ty, [$($g:tt)*]) => {
+        impl<$($g)*, $($cg)*> TryFrom<$t> for $name<$t $(, $c)?> {
+            type Error = $crate::ValidationError;
+
+            fn try_from(t: $t) -> Result<Self, Self::Error> {
+                Self::try_new(t)
+            }
+        }
+    };
+}
@@ -0,0 +1,256 @@
+//! Mahalanobis distance through a whitening transform.
+
+use crate::{
+    validate_within, Coordinates, DatasetError, Element, FastEuclidean, FastEuclideanDataset,
+    ValidationError, WhiteningError,
+};
+
+/// Transform mapping points into a space where Euclidean distance is Mahalanobis distance.
+///
+/// The transform is fitted once from a sample, computing its mean `μ` and the Cholesky factor `L`
+/// of its covariance matrix `Σ = LLᵀ`. Each point `x` is then mapped to `L⁻¹(x - μ)`, and the
+/// Euclidean distance between two mapped points equals the Mahalanobis distance
+/// `√((x - y)ᵀ Σ⁻¹ (x - y))` between the originals. Mapped points are ordinary
+/// [`FastEuclidean`] points, so they can be indexed as usual, and queries mapped with the same
+/// transform before searching.
+/// # Example
+/// ```
+/// # use bitpart::metric::Metric;
+/// # use bitpart_fast_euclidean::Whitening;
+/// let sample = [[0.0, 0.0], [2.0, 0.0], [0.0, 4.0], [2.0, 4.0]];
+/// let whitening = Whitening::fit(&sample).unwrap();
+///
+/// // The second coordinate varies twice as much, so counts for half as much.
+/// let origin = whitening.whiten(&[1.0, 2.0]).unwrap();
+/// let x = whitening.whiten(&[2.0, 2.0]).unwrap();
+/// let y = whitening.whiten(&[1.0, 4.0]).unwrap();
+/// assert!((origin.distance(&x) - origin.distance(&y)).abs() < 1e-12);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Whitening {
+    mean: Vec<f64>,
+    /// Lower-triangular Cholesky factor of the covariance matrix, stored row-major.
+    factor: Vec<f64>,
+}
+
+impl Whitening {
+    /// Fits the transform to the sample covariance of `sample`.
+    ///
+    /// The sample must contain enough varied points for its covariance matrix to be
+    /// positive-definite, which needs at least one more point than its dimension. Otherwise see
+    /// [`fit_regularised`](Self::fit_regularised).
+    pub fn fit<I>(sample: I) -> Result<Self, WhiteningError>
+    where
+        I: IntoIterator,
+        I::Item: Coordinates,
+    {
+        Self::fit_regularised(sample, 0.0)
+    }
+
+    /// Fits the transform to the sample covariance of `sample`, with `ridge` added to each
+    /// variance.
+    ///
+    /// A small positive `ridge` makes the covariance matrix positive-definite even when some
+    /// coordinates are constant or correlated across the sample.
+    /// # Panics
+    /// Panics if `ridge` is negative or not finite.
+    pub fn fit_regularised<I>(sample: I, ridge: f64) -> Result<Self, WhiteningError>
+    where
+        I: IntoIterator,
+        I::Item: Coordinates,
+    {
+        assert!(
+            ridge.is_finite() && ridge >= 0.0,
+            "the ridge must be finite and non-negative"
+        );
+        let mut covariance = Covariance::default();
+        for (index, point) in sample.into_iter().enumerate() {
+            covariance.add(index, point.coordinates())?;
+        }
+        if covariance.count == 0 {
+            return Err(WhiteningError::Empty);
+        }
+        let dimension = covariance.mean.len();
+        if dimension == 0 {
+            return Err(WhiteningError::ZeroDimension);
+        }
+        let mut matrix = covariance.finish();
+        for i in 0..dimension {
+            matrix[i * dimension + i] += ridge;
+        }
+        Ok(Self {
+            factor: cholesky(matrix, dimension)?,
+            mean: covariance.mean,
+        })
+    }
+
+    /// Returns the dimension of the points the transform applies to.
+    pub fn dimension(&self) -> usize {
+        self.mean.len()
+    }
+
+    /// Returns the mean of the sample the transform was fitted to.
+    pub fn mean(&self) -> &[f64] {
+        &self.mean
+    }
+
+    /// Maps `point` into whitened space.
+    ///
+    /// Fails if the point has the wrong dimension or a coordinate that is not finite, or if the
+    /// mapped point is too far from the sample to satisfy the [`FastEuclidean`] safety contract.
+    pub fn whiten<C>(&self, point: &C) -> Result<FastEuclidean<Vec<f64>>, ValidationError>
+    where
+        C: Coordinates + ?Sized,
+    {
+        let coordinates = point.coordinates();
+        if coordinates.len() != self.dimension() {
+            return Err(ValidationError::Dimension {
+                expected: self.dimension(),
+                found: coordinates.len(),
+            });
+        }
+        validate_within(coordinates, f64::INFINITY)?;
+        let n = self.dimension();
+        let mut whitened = Vec::with_capacity(n);
+        // Forward substitution, solving Lz = x - μ one row at a time.
+        for i in 0..n {
+            let row = &self.factor[i * n..i * n + n];
+            let sum = row[..i]
+                .iter()
+                .zip(&whitened)
+                .fold(coordinates[i].to_f64() - self.mean[i], |sum, (l, z)| {
+                    sum - l * z
+                });
+            whitened.push(sum / row[i]);
+        }
+        FastEuclidean::try_new(whitened)
+    }
+
+    /// Maps every point from `points` into whitened space, collecting them into a dataset.
+    ///
+    /// Stops at the first point that cannot be mapped, as for [`whiten`](Self::whiten).
+    pub fn whiten_all<I>(&self, points: I) -> Result<FastEuclideanDataset<f64>, DatasetError>
+    where
+        I: IntoIterator,
+        I::Item: Coordinates,
+    {
+        let points = points.into_iter();
+        let mut data = Vec::with_capacity(points.size_hint().0.saturating_mul(self.dimension()));
+        for (index, point) in points.enumerate() {
+            match self.whiten(&point) {
+                Ok(whitened) => data.extend_from_slice(&whitened),
+                Err(ValidationError::Dimension { expected, found }) => {
+                    return Err(DatasetError::Dimension {
+                        point: index,
+                        expected,
+                        found,
+                    })
+                }
+                Err(source) => {
+                    return Err(DatasetError::Invalid {
+                        point: index,
+                        source,
+                    })
+                }
+            }
+        }
+        // SAFETY: every point was validated by `whiten`, and has `dimension` coordinates.
+        Ok(unsafe { FastEuclideanDataset::from_flat_unchecked(self.dimension(), data) })
+    }
+}
+
+/// Running mean and scatter matrix of a sample, updated one point at a time with Welford's
+/// algorithm to avoid cancellation.
+#[derive(Default)]
+struct Covariance {
+    count: usize,
+    mean: Vec<f64>,
+    /// Lower triangle of the sum of outer products of deviations from the mean, stored row-major.
+    scatter: Vec<f64>,
+}
+
+impl Covariance {
+    fn add<E: Element>(&mut self, index: usize, coordinates: &[E]) -> Result<(), DatasetError> {
+        if self.count == 0 {
+            let n = coordinates.len();
+            self.mean = vec![0.0; n];
+            self.scatter = vec![0.0; n * n];
+        }
+        let n = self.mean.len();
+        if coordinates.len() != n {
+            return Err(DatasetError::Dimension {
+                point: index,
+                expected: n,
+                found: coordinates.len(),
+            });
+        }
+        validate_within(coordinates, f64::INFINITY).map_err(|source| DatasetError::Invalid {
+            point: index,
+            source,
+        })?;
+        self.count += 1;
+        let delta: Vec<f64> = coordinates
+            .iter()
+            .zip(&mut self.mean)
+            .map(|(x, mean)| {
+                let delta = x.to_f64() - *mean;
+                *mean += delta / self.count as f64;
+                delta
+            })
+            .collect();
+        for (i, delta) in delta.into_iter().enumerate() {
+            let row = &mut self.scatter[i * n..=i * n + i];
+            for ((s, x), mean) in row.iter_mut().zip(coordinates).zip(&self.mean) {
+                *s += delta * (x.to_f64() - mean);
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the lower triangle of the sample covariance matrix.
+    fn finish(&self) -> Vec<f64> {
+        let divisor = self.count.saturating_sub(1).max(1) as f64;
+        self.scatter.iter().map(|s| s / divisor).collect()
+    }
+}
+
+/// Computes the lower-triangular Cholesky factor of the symmetric matrix whose lower triangle is
+/// stored row-major in `matrix`, overwriting it in place.
+///
+/// Pivots that are not positive, or are within rounding error of zero relative to the variance of
+/// their column, are rejected, as whitening with them would amplify rounding errors without bound.
+fn cholesky(mut matrix: Vec<f64>, n: usize) -> Result<Vec<f64>, WhiteningError> {
+    for j in 0..n {
+        let (above, rest) = matrix.split_at_mut(j * n);
+        let row = &mut rest[..n];
+        for k in 0..j {
+            let previous = &above[k * n..k * n + n];
+            let dot: f64 = row[..k].iter().zip(previous).map(|(a, b)| a * b).sum();
+            row[k] = (row[k] - dot) / previous[k];
+        }
+        let diagonal = row[j];
+        let pivot = diagonal - row[..j].iter().map(|l| l * l).sum::<f64>();
+        if pivot.is_nan() || pivot <= diagonal * n as f64 * f64::EPSILON {
+            return Err(WhiteningError::NotPositiveDefinite { column: j });
+        }
+        row[j] = pivot.sqrt();
+    }
+    Ok(matrix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_degenerate_samples() {
+        let empty: [[f64; 2]; 0] = [];
+        assert_eq!(Whitening::fit(empty), Err(WhiteningError::Empty));
+        let points: [[f64; 0]; 3] = [[]; 3];
+        assert_eq!(Whitening::fit(points), Err(WhiteningError::ZeroDimension));
+        assert_eq!(
+            Whitening::fit([[1.0, 0.0], [1.0, 1.0], [1.0, 2.0]]),
+            Err(WhiteningError::NotPositiveDefinite { column: 0 })
+        );
+    }
+}
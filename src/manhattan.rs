@@ -0,0 +1,64 @@
+use bitpart::metric::Metric;
+
+use crate::element::private::Sealed;
+use crate::kernel::Manhattan;
+use crate::{limit_for_sum, validate_within, Coordinates, ValidationError};
+
+/// Wrapper struct to apply Manhattan (L1) distance to an object set.
+///
+/// This is the L1 counterpart of [`FastEuclidean`](crate::FastEuclidean), with the same
+/// constructor contract and the same unchecked, vectorised reduction. It suits histograms and
+/// counts, where differences in each coordinate add up linearly.
+/// # Example
+/// ```
+/// # use bitpart::metric::Metric;
+/// # use bitpart_fast_euclidean::FastManhattan;
+/// let point1 = FastManhattan::<[f64; 2]>::try_new([0.0, 0.0]).unwrap();
+/// let point2 = FastManhattan::<[f64; 2]>::try_new([3.0, -4.0]).unwrap();
+///
+/// assert_eq!(point1.distance(&point2), 7.0);
+/// ```
+#[derive(Debug, Clone)]
+pub struct FastManhattan<T>(T);
+
+impl<T> FastManhattan<T> {
+    /// Creates a new `FastManhattan`.
+    /// # Safety
+    /// You **must** ensure that the Manhattan distance measurements between
+    /// any two points cannot return [`NaN`](f64::NAN) nor [`INF`](f64::INFINITY).
+    /// For `f32` coordinates, the distance must also fit in an `f32`.
+    pub unsafe fn new(t: T) -> Self {
+        Self(t)
+    }
+}
+
+impl<T> FastManhattan<T>
+where
+    T: Coordinates,
+{
+    /// Creates a new `FastManhattan`, checking that the safety requirements of [`new`](Self::new)
+    /// are upheld.
+    ///
+    /// Every coordinate must be finite, and small enough in magnitude that the distance to any
+    /// other point of the same dimension passing this check cannot overflow.
+    pub fn try_new(t: T) -> Result<Self, ValidationError> {
+        let coordinates = t.coordinates();
+        let limit = limit_for_sum(T::Element::ACCUMULATOR_MAX, coordinates.len(), 1.0, 1.0);
+        validate_within(coordinates, limit)?;
+        Ok(Self(t))
+    }
+}
+
+impl_wrapper!(FastManhattan);
+
+/// Bounds as for [`FastEuclidean`](crate::FastEuclidean).
+impl<T> Metric for FastManhattan<T>
+where
+    T: Coordinates + Clone,
+{
+    fn distance(&self, rhs: &FastManhattan<T>) -> f64 {
+        let (lhs, rhs) = (self.0.coordinates(), rhs.0.coordinates());
+        debug_assert_eq!(lhs.len(), rhs.len(), "points have different dimensions");
+        T::Element::reduce::<Manhattan>(lhs, rhs)
+    }
+}
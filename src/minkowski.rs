@@ -0,0 +1,266 @@
+use bitpart::metric::Metric;
+use std::ops::Deref;
+
+#[cfg(feature = "serde")]
+use serde::{de::Error, Deserialize, Deserializer, Serialize};
+
+use crate::element::private::Sealed;
+use crate::kernel::{self, Power};
+use crate::{
+    check_dimensions, limit_for_sum, validate_within, Coordinates, Element, PointMismatch,
+    ValidationError,
+};
+
+/// Wrapper struct to apply Minkowski distance with integer exponent `P` to an object set.
+///
+/// The distance is `(Σ|xᵢ - yᵢ|ᴾ)^(1/P)`, computed with the same constructor contract and
+/// unchecked, vectorised reduction as [`FastEuclidean`](crate::FastEuclidean). Exponents up to 4
+/// are computed with multiplications alone. `P` must be at least 1, which is checked at compile
+/// time. For fractional exponents, see [`FastMinkowskiDyn`].
+/// # Example
+/// ```
+/// # use bitpart::metric::Metric;
+/// # use bitpart_fast_euclidean::FastMinkowski;
+/// let point1 = FastMinkowski::<[f64; 2], 3>::try_new([0.0, 0.0]).unwrap();
+/// let point2 = FastMinkowski::<[f64; 2], 3>::try_new([3.0, 3.0]).unwrap();
+///
+/// assert!((point1.distance(&point2) - 54.0_f64.cbrt()).abs() < 1e-12);
+/// ```
+#[derive(Debug, Clone)]
+pub struct FastMinkowski<T, const P: u32>(T);
+
+impl<T, const P: u32> FastMinkowski<T, P> {
+    /// The exponent of the distance.
+    pub const EXPONENT: u32 = {
+        assert!(P >= 1, "the Minkowski exponent must be at least 1");
+        P
+    };
+
+    /// Creates a new `FastMinkowski`.
+    /// # Safety
+    /// You **must** ensure that the Minkowski distance measurements between
+    /// any two points cannot return [`NaN`](f64::NAN) nor [`INF`](f64::INFINITY).
+    /// For `f32` coordinates, the sum of powers must also fit in an `f32`.
+    pub unsafe fn new(t: T) -> Self {
+        let _ = Self::EXPONENT;
+        Self(t)
+    }
+}
+
+impl<T, const P: u32> FastMinkowski<T, P>
+where
+    T: Coordinates,
+{
+    /// Creates a new `FastMinkowski`, checking that the safety requirements of [`new`](Self::new)
+    /// are upheld.
+    ///
+    /// Every coordinate must be finite, and small enough in magnitude that the sum of powers for
+    /// any other point of the same dimension passing this check cannot overflow.
+    pub fn try_new(t: T) -> Result<Self, ValidationError> {
+        let coordinates = t.coordinates();
+        let limit = coordinate_limit::<T::Element>(coordinates.len(), Self::EXPONENT as f64);
+        validate_within(coordinates, limit)?;
+        Ok(Self(t))
+    }
+}
+
+impl_wrapper!(FastMinkowski, const P: u32);
+
+/// Bounds as for [`FastEuclidean`](crate::FastEuclidean).
+impl<T, const P: u32> Metric for FastMinkowski<T, P>
+where
+    T: Coordinates + Clone,
+{
+    fn distance(&self, rhs: &FastMinkowski<T, P>) -> f64 {
+        let (lhs, rhs) = (self.0.coordinates(), rhs.0.coordinates());
+        debug_assert_eq!(lhs.len(), rhs.len(), "points have different dimensions");
+        let sum = T::Element::reduce::<Power<P>>(lhs, rhs);
+        match Self::EXPONENT {
+            1 => sum,
+            2 => sum.sqrt(),
+            3 => sum.cbrt(),
+            4 => sum.sqrt().sqrt(),
+            p => sum.powf((p as f64).recip()),
+        }
+    }
+}
+
+/// Wrapper struct to apply Minkowski distance with an exponent chosen at runtime to an object set.
+///
+/// The exponent `p` may be fractional, but must be at least 1 for the distance to be a metric.
+/// It is stored with each point, and both points of a comparison must share it. Integer exponents
+/// up to 4 use the same kernels as [`FastMinkowski`]; others are accumulated in `f64`.
+/// # Example
+/// ```
+/// # use bitpart::metric::Metric;
+/// # use bitpart_fast_euclidean::FastMinkowskiDyn;
+/// let point1 = FastMinkowskiDyn::try_new(vec![0.0, 0.0], 1.5).unwrap();
+/// let point2 = FastMinkowskiDyn::try_new(vec![1.0, 1.0], 1.5).unwrap();
+///
+/// assert!((point1.distance(&point2) - 2.0_f64.powf(1.0 / 1.5)).abs() < 1e-12);
+/// ```
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct FastMinkowskiDyn<T> {
+    point: T,
+    p: f64,
+}
+
+impl<T> FastMinkowskiDyn<T> {
+    /// Creates a new `FastMinkowskiDyn` with exponent `p`.
+    /// # Safety
+    /// The same requirements as [`FastMinkowski::new`] apply.
+    /// # Panics
+    /// Panics if `p` is less than 1 or not finite.
+    pub unsafe fn new(t: T, p: f64) -> Self {
+        assert!(
+            valid_exponent(p),
+            "the Minkowski exponent must be at least 1"
+        );
+        Self { point: t, p }
+    }
+
+    /// Returns the exponent of the distance.
+    pub fn p(&self) -> f64 {
+        self.p
+    }
+
+    /// Returns the wrapped point.
+    pub fn into_inner(self) -> T {
+        self.point
+    }
+}
+
+impl<T> FastMinkowskiDyn<T>
+where
+    T: Coordinates,
+{
+    /// Creates a new `FastMinkowskiDyn` with exponent `p`, checking the requirements of
+    /// [`FastMinkowski::try_new`].
+    /// # Panics
+    /// Panics if `p` is less than 1 or not finite.
+    pub fn try_new(t: T, p: f64) -> Result<Self, ValidationError> {
+        assert!(
+            valid_exponent(p),
+            "the Minkowski exponent must be at least 1"
+        );
+        let coordinates = t.coordinates();
+        validate_within(
+            coordinates,
+            coordinate_limit::<T::Element>(coordinates.len(), p),
+        )?;
+        Ok(Self { point: t, p })
+    }
+
+    /// Calculates the distance to `rhs`, first checking that both points have the same dimension
+    /// and the same exponent.
+    pub fn try_distance(&self, rhs: &Self) -> Result<f64, PointMismatch>
+    where
+        T: Clone,
+    {
+        check_dimensions(self.point.coordinates(), rhs.point.coordinates())?;
+        if self.p != rhs.p {
+            return Err(PointMismatch::Exponent {
+                left: self.p,
+                right: rhs.p,
+            });
+        }
+        Ok(self.distance(rhs))
+    }
+}
+
+impl<T> Deref for FastMinkowskiDyn<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.point
+    }
+}
+
+/// Bounds as for [`FastEuclidean`](crate::FastEuclidean).
+///
+/// Each point is only validated against its own exponent, so comparing points with different
+/// exponents could overflow. `distance` therefore panics if the exponents differ.
+impl<T> Metric for FastMinkowskiDyn<T>
+where
+    T: Coordinates + Clone,
+{
+    fn distance(&self, rhs: &FastMinkowskiDyn<T>) -> f64 {
+        let (x, y) = (self.point.coordinates(), rhs.point.coordinates());
+        debug_assert_eq!(x.len(), y.len(), "points have different dimensions");
+        assert_eq!(self.p, rhs.p, "points have different exponents");
+        if self.p == 1.0 {
+            T::Element::reduce::<Power<1>>(x, y)
+        } else if self.p == 2.0 {
+            T::Element::reduce::<Power<2>>(x, y).sqrt()
+        } else if self.p == 3.0 {
+            T::Element::reduce::<Power<3>>(x, y).cbrt()
+        } else if self.p == 4.0 {
+            T::Element::reduce::<Power<4>>(x, y).sqrt().sqrt()
+        } else {
+            kernel::minkowski_wide(x, y, self.p).powf(self.p.recip())
+        }
+    }
+}
+
+/// The exponent is checked before the point is validated.
+#[cfg(feature = "serde")]
+impl<'de, T> Deserialize<'de> for FastMinkowskiDyn<T>
+where
+    T: Deserialize<'de> + Coordinates,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(rename = "FastMinkowskiDyn")]
+        struct Unvalidated<T> {
+            point: T,
+            p: f64,
+        }
+
+        let Unvalidated { point, p } = Unvalidated::<T>::deserialize(deserializer)?;
+        if !valid_exponent(p) {
+            return Err(D::Error::custom(format!(
+                "Minkowski exponent {p} is not at least 1"
+            )));
+        }
+        Self::try_new(point, p).map_err(D::Error::custom)
+    }
+}
+
+fn valid_exponent(p: f64) -> bool {
+    p.is_finite() && p >= 1.0
+}
+
+/// Largest coordinate magnitude accepted for a point of the given dimension and exponent.
+fn coordinate_limit<E: Element>(dimension: usize, p: f64) -> f64 {
+    limit_for_sum(E::ACCUMULATOR_MAX, dimension, p, 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_different_exponents() {
+        let point1 = FastMinkowskiDyn::try_new(vec![0.0], 10.0).unwrap();
+        let point2 = FastMinkowskiDyn::try_new(vec![1e200], 1.5).unwrap();
+        assert_eq!(
+            point1.try_distance(&point2),
+            Err(PointMismatch::Exponent {
+                left: 10.0,
+                right: 1.5
+            })
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "points have different exponents")]
+    fn distance_panics_on_different_exponents() {
+        let point1 = FastMinkowskiDyn::try_new([0.0], 10.0).unwrap();
+        let point2 = FastMinkowskiDyn::try_new([1.0], 1.5).unwrap();
+        point1.distance(&point2);
+    }
+}
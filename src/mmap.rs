@@ -0,0 +1,357 @@
+//! Read-only datasets memory-mapped from disk.
+//!
+//! A dataset file is a 64-byte header followed by every point's coordinates, row-major, as raw
+//! little-endian values. The header contains, in order and little-endian:
+//!
+//! | Offset | Size | Field                                      |
+//! |--------|------|--------------------------------------------|
+//! | 0      | 8    | Magic bytes `BPFEDATA`                     |
+//! | 8      | 4    | Format version, currently `1`              |
+//! | 12     | 4    | Element type (`1` = f32, `2` = f64, `3` = f16, `4` = bf16) |
+//! | 16     | 8    | Dimension of each point                    |
+//! | 24     | 8    | Number of points                           |
+//! | 32     | 32   | Reserved, zero                             |
+//!
+//! Coordinates are used in place, so the files can only be read and written on little-endian
+//! targets.
+
+use std::fs::File;
+use std::io::{Seek, SeekFrom, Write};
+use std::marker::PhantomData;
+use std::mem::{align_of, size_of};
+use std::path::Path;
+
+use memmap2::Mmap;
+
+use crate::{
+    validate, Coordinates, DatasetError, Element, FastEuclidean, FastEuclideanDatasetView,
+    FileError, Points,
+};
+
+const MAGIC: &[u8; 8] = b"BPFEDATA";
+const VERSION: u32 = 1;
+const HEADER_LEN: usize = 64;
+
+/// A read-only dataset backed by a memory-mapped file.
+///
+/// Points are handed out as [`FastEuclidean<&[E]>`](FastEuclidean) views into the mapping, so a
+/// dataset larger than memory is paged in by the operating system as it is searched.
+/// # Example
+/// ```no_run
+/// # use bitpart_fast_euclidean::mmap::MmapDataset;
+/// // SAFETY: nothing else modifies the file while it is mapped.
+/// let dataset = unsafe { MmapDataset::<f32>::open("points.bpfe") }.unwrap();
+/// for point in dataset.iter() {
+///     // ...
+/// }
+/// ```
+#[derive(Debug)]
+pub struct MmapDataset<E = f64> {
+    mmap: Mmap,
+    dimension: usize,
+    _element: PhantomData<E>,
+}
+
+impl<E> MmapDataset<E>
+where
+    E: Element,
+{
+    /// Maps the dataset file at `path`, validating its header and every point.
+    /// # Safety
+    /// The file must not be modified or truncated while the dataset exists. See
+    /// [`Mmap::map`].
+    pub unsafe fn open<P: AsRef<Path>>(path: P) -> Result<Self, FileError> {
+        Self::from_mmap(Mmap::map(&File::open(path)?)?)
+    }
+
+    /// Maps the dataset file at `path`, validating its header but not its points.
+    /// # Safety
+    /// The file must not be modified or truncated while the dataset exists, and every point
+    /// must satisfy the requirements of [`FastEuclidean::new`].
+    pub unsafe fn open_unchecked<P: AsRef<Path>>(path: P) -> Result<Self, FileError> {
+        let mmap = Mmap::map(&File::open(path)?)?;
+        let dimension = check_header::<E>(&mmap)?;
+        Ok(Self {
+            mmap,
+            dimension,
+            _element: PhantomData,
+        })
+    }
+
+    /// Wraps an existing mapping of a dataset file, validating its header and every point.
+    ///
+    /// Fails with [`FileError::Format`] if the coordinates are not aligned for `E`, which can
+    /// happen when the mapping starts at an offset into the file.
+    pub fn from_mmap(mmap: Mmap) -> Result<Self, FileError> {
+        let dimension = check_header::<E>(&mmap)?;
+        let dataset = Self {
+            mmap,
+            dimension,
+            _element: PhantomData,
+        };
+        for (point, coordinates) in dataset.coordinates().chunks_exact(dimension).enumerate() {
+            validate(coordinates).map_err(|source| DatasetError::Invalid { point, source })?;
+        }
+        Ok(dataset)
+    }
+
+    /// Returns a view of the whole dataset.
+    pub fn view(&self) -> FastEuclideanDatasetView<'_, E> {
+        // SAFETY: the header was checked and the points validated, or the caller vouched for
+        // them, when the dataset was created.
+        unsafe { FastEuclideanDatasetView::from_flat_unchecked(self.dimension, self.coordinates()) }
+    }
+
+    /// Returns the number of coordinates of each point.
+    pub fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    /// Returns the number of points in the dataset.
+    pub fn len(&self) -> usize {
+        self.view().len()
+    }
+
+    /// Returns `true` if the dataset contains no points.
+    pub fn is_empty(&self) -> bool {
+        self.view().is_empty()
+    }
+
+    /// Returns the point at `index`, or `None` if it is out of bounds.
+    pub fn get(&self, index: usize) -> Option<FastEuclidean<&[E]>> {
+        self.view().get(index)
+    }
+
+    /// Returns an iterator over the points in the dataset.
+    pub fn iter(&self) -> Points<'_, E> {
+        self.view().iter()
+    }
+
+    fn coordinates(&self) -> &[E] {
+        let body = &self.mmap[HEADER_LEN..];
+        // SAFETY: `check_header` ensured the body holds a whole number of elements and is
+        // aligned for them, and every bit pattern is a valid element.
+        unsafe { std::slice::from_raw_parts(body.as_ptr().cast(), body.len() / size_of::<E>()) }
+    }
+}
+
+impl<'a, E> IntoIterator for &'a MmapDataset<E>
+where
+    E: Element,
+{
+    type Item = FastEuclidean<&'a [E]>;
+    type IntoIter = Points<'a, E>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Checks the header of a mapped dataset file, returning the dimension of its points.
+fn check_header<E: Element>(bytes: &[u8]) -> Result<usize, FileError> {
+    check_endian()?;
+    let header = bytes
+        .get(..HEADER_LEN)
+        .ok_or_else(|| FileError::Format("file is shorter than its header".into()))?;
+    if &header[..8] != MAGIC {
+        return Err(FileError::Format("missing magic bytes".into()));
+    }
+    let version = u32::from_le_bytes(header[8..12].try_into().unwrap());
+    if version != VERSION {
+        return Err(FileError::Format(format!("unsupported version {version}")));
+    }
+    let type_code = u32::from_le_bytes(header[12..16].try_into().unwrap());
+    if type_code != E::TYPE_CODE {
+        return Err(FileError::Format(format!(
+            "element type {type_code} does not match the requested type {}",
+            E::TYPE_CODE
+        )));
+    }
+    let dimension = read_u64(&header[16..24])?;
+    let count = read_u64(&header[24..32])?;
+    if dimension == 0 {
+        return Err(FileError::Format("dimension must be non-zero".into()));
+    }
+    let expected = dimension
+        .checked_mul(count)
+        .and_then(|n| n.checked_mul(size_of::<E>()))
+        .ok_or_else(|| FileError::Format("dataset size overflows".into()))?;
+    let body = &bytes[HEADER_LEN..];
+    if body.len() != expected {
+        return Err(FileError::Format(format!(
+            "expected {expected} bytes of coordinates, found {}",
+            body.len()
+        )));
+    }
+    // Mappings made at an offset into the file need not be aligned.
+    if body.as_ptr().align_offset(align_of::<E>()) != 0 {
+        return Err(FileError::Format(
+            "coordinates are not aligned in memory".into(),
+        ));
+    }
+    Ok(dimension)
+}
+
+fn read_u64(bytes: &[u8]) -> Result<usize, FileError> {
+    usize::try_from(u64::from_le_bytes(bytes.try_into().unwrap()))
+        .map_err(|_| FileError::Format("size does not fit in memory".into()))
+}
+
+fn check_endian() -> Result<(), FileError> {
+    if cfg!(target_endian = "little") {
+        Ok(())
+    } else {
+        Err(FileError::Format(
+            "dataset files are only supported on little-endian targets".into(),
+        ))
+    }
+}
+
+/// Writes `points` to `writer` as a dataset file that can be opened by [`MmapDataset`].
+///
+/// The header is written first with a placeholder count, which is filled in once every point has
+/// been written. Returns the number of points written.
+/// # Example
+/// ```no_run
+/// # use bitpart_fast_euclidean::{mmap, FastEuclidean};
+/// let points = [[0.0, 1.0], [2.0, 3.0]].map(|p| FastEuclidean::<[f64; 2]>::try_new(p).unwrap());
+/// let file = std::fs::File::create("points.bpfe").unwrap();
+/// mmap::write(std::io::BufWriter::new(file), 2, points).unwrap();
+/// ```
+pub fn write<W, I, T>(mut writer: W, dimension: usize, points: I) -> Result<usize, FileError>
+where
+    W: Write + Seek,
+    I: IntoIterator<Item = FastEuclidean<T>>,
+    T: Coordinates,
+{
+    check_endian()?;
+    if dimension == 0 {
+        return Err(FileError::Format("dimension must be non-zero".into()));
+    }
+    let start = writer.stream_position()?;
+    writer.write_all(&header::<T::Element>(dimension, 0))?;
+
+    let mut count = 0;
+    for point in points {
+        let coordinates = point.coordinates();
+        if coordinates.len() != dimension {
+            return Err(DatasetError::Dimension {
+                point: count,
+                expected: dimension,
+                found: coordinates.len(),
+            }
+            .into());
+        }
+        // SAFETY: every element type is plain data, with no padding.
+        let bytes = unsafe {
+            std::slice::from_raw_parts(
+                coordinates.as_ptr().cast::<u8>(),
+                std::mem::size_of_val(coordinates),
+            )
+        };
+        writer.write_all(bytes)?;
+        count += 1;
+    }
+
+    let end = writer.stream_position()?;
+    writer.seek(SeekFrom::Start(start))?;
+    writer.write_all(&header::<T::Element>(dimension, count))?;
+    writer.seek(SeekFrom::Start(end))?;
+    writer.flush()?;
+    Ok(count)
+}
+
+fn header<E: Element>(dimension: usize, count: usize) -> [u8; HEADER_LEN] {
+    let mut header = [0; HEADER_LEN];
+    header[..8].copy_from_slice(MAGIC);
+    header[8..12].copy_from_slice(&VERSION.to_le_bytes());
+    header[12..16].copy_from_slice(&E::TYPE_CODE.to_le_bytes());
+    header[16..24].copy_from_slice(&(dimension as u64).to_le_bytes());
+    header[24..32].copy_from_slice(&(count as u64).to_le_bytes());
+    header
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    fn points() -> [FastEuclidean<[f32; 3]>; 2] {
+        [[0.0, 1.0, 2.0], [3.0, 4.0, 5.0]].map(|p| FastEuclidean::try_new(p).unwrap())
+    }
+
+    /// Writes the test points to an anonymous file, lets `edit` modify its bytes, and maps it.
+    fn mapped(edit: impl FnOnce(&mut Vec<u8>)) -> Mmap {
+        let mut file = tempfile::tempfile().unwrap();
+        assert_eq!(write(&mut file, 3, points()).unwrap(), 2);
+        let mut bytes = Vec::new();
+        file.seek(SeekFrom::Start(0)).unwrap();
+        file.read_to_end(&mut bytes).unwrap();
+        edit(&mut bytes);
+        file.set_len(0).unwrap();
+        file.seek(SeekFrom::Start(0)).unwrap();
+        file.write_all(&bytes).unwrap();
+        // SAFETY: the file is only reachable from this test.
+        unsafe { Mmap::map(&file) }.unwrap()
+    }
+
+    fn expect_format<E: Element + std::fmt::Debug>(mmap: Mmap, message: &str) {
+        match MmapDataset::<E>::from_mmap(mmap) {
+            Err(FileError::Format(m)) => assert!(m.contains(message), "{m}"),
+            other => panic!("expected {message:?}, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        write(file.as_file(), 3, points()).unwrap();
+        // SAFETY: the file is only reachable from this test.
+        let dataset = unsafe { MmapDataset::<f32>::open(file.path()) }.unwrap();
+        assert_eq!(dataset.dimension(), 3);
+        assert_eq!(dataset.len(), 2);
+        assert_eq!(*dataset.get(1).unwrap(), [3.0, 4.0, 5.0]);
+        assert_eq!(dataset.view().as_flat(), [0.0, 1.0, 2.0, 3.0, 4.0, 5.0]);
+
+        let dataset = MmapDataset::<f32>::from_mmap(mapped(|_| {})).unwrap();
+        assert_eq!(dataset.iter().count(), 2);
+    }
+
+    #[test]
+    fn rejects_bad_headers() {
+        expect_format::<f64>(mapped(|_| {}), "element type 1");
+        expect_format::<f32>(
+            mapped(|bytes| bytes.truncate(HEADER_LEN + 20)),
+            "expected 24",
+        );
+        expect_format::<f32>(mapped(|bytes| bytes.push(0)), "expected 24");
+        expect_format::<f32>(mapped(|bytes| bytes[16..24].fill(0)), "non-zero");
+        expect_format::<f32>(mapped(|bytes| bytes[0] = b'X'), "magic");
+        expect_format::<f32>(mapped(|bytes| bytes.truncate(10)), "shorter");
+    }
+
+    #[test]
+    fn rejects_invalid_points() {
+        let mmap = mapped(|bytes| {
+            let offset = HEADER_LEN + 4 * size_of::<f32>();
+            bytes[offset..offset + 4].copy_from_slice(&f32::INFINITY.to_le_bytes());
+        });
+        assert!(matches!(
+            MmapDataset::<f32>::from_mmap(mmap),
+            Err(FileError::Dataset(DatasetError::Invalid { point: 1, .. }))
+        ));
+    }
+
+    #[test]
+    fn write_rejects_bad_dimensions() {
+        let mut file = tempfile::tempfile().unwrap();
+        assert!(matches!(
+            write(&mut file, 0, points()),
+            Err(FileError::Format(_))
+        ));
+        assert!(matches!(
+            write(&mut file, 2, points()),
+            Err(FileError::Dataset(DatasetError::Dimension { point: 0, .. }))
+        ));
+    }
+}
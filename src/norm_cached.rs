@@ -0,0 +1,209 @@
+use bitpart::metric::Metric;
+use std::ops::Deref;
+
+#[cfg(feature = "serde")]
+use serde::{de::Error, Deserialize, Deserializer, Serialize};
+
+use crate::pairwise::{from_norms, squared_norm};
+use crate::{kernel, validate, Coordinates, FastEuclidean, ValidationError};
+
+/// Wrapper struct to apply Euclidean distance to an object set, caching each point's squared norm.
+///
+/// Distances are calculated as `‖x‖² + ‖y‖² − 2x·y`, so only the dot product is computed per
+/// comparison. Pairs close enough for cancellation to dominate the result fall back to the
+/// difference formula. With the `serde` feature, the cached norm is stored alongside the point,
+/// and checked against a recomputed norm when the point is deserialised, unless it is loaded as
+/// is from a trusted input through [`unchecked`](crate::unchecked).
+/// # Example
+/// ```
+/// # use bitpart::metric::Metric;
+/// # use bitpart_fast_euclidean::NormCachedEuclidean;
+/// let point1 = NormCachedEuclidean::<[f64; 2]>::try_new([0.0, 0.0]).unwrap();
+/// let point2 = NormCachedEuclidean::<[f64; 2]>::try_new([3.0, 4.0]).unwrap();
+///
+/// assert_eq!(point2.squared_norm(), 25.0);
+/// assert_eq!(point1.distance(&point2), 5.0);
+/// ```
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct NormCachedEuclidean<T> {
+    point: T,
+    squared_norm: f64,
+}
+
+impl<T> NormCachedEuclidean<T>
+where
+    T: Coordinates,
+{
+    /// Creates a new `NormCachedEuclidean`.
+    /// # Safety
+    /// The same requirements as [`FastEuclidean::new`] apply.
+    pub unsafe fn new(t: T) -> Self {
+        let squared_norm = squared_norm(t.coordinates());
+        Self {
+            point: t,
+            squared_norm,
+        }
+    }
+
+    /// Creates a new `NormCachedEuclidean`, checking the requirements of
+    /// [`FastEuclidean::try_new`].
+    pub fn try_new(t: T) -> Result<Self, ValidationError> {
+        validate(t.coordinates())?;
+        Ok(unsafe { Self::new(t) })
+    }
+
+    /// Returns the cached squared norm of the point.
+    pub fn squared_norm(&self) -> f64 {
+        self.squared_norm
+    }
+
+    /// Returns the wrapped point, discarding the cached norm.
+    pub fn into_inner(self) -> T {
+        self.point
+    }
+}
+
+impl<T> From<FastEuclidean<T>> for NormCachedEuclidean<T>
+where
+    T: Coordinates,
+{
+    fn from(point: FastEuclidean<T>) -> Self {
+        // SAFETY: `point` already upholds the contract of `FastEuclidean::new`.
+        unsafe { Self::new(point.0) }
+    }
+}
+
+impl<T> Deref for NormCachedEuclidean<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.point
+    }
+}
+
+impl<T> Metric for NormCachedEuclidean<T>
+where
+    T: Coordinates + Clone,
+{
+    fn distance(&self, rhs: &NormCachedEuclidean<T>) -> f64 {
+        let (x, y) = (self.point.coordinates(), rhs.point.coordinates());
+        debug_assert_eq!(x.len(), y.len(), "points have different dimensions");
+        from_norms(self.squared_norm, rhs.squared_norm, kernel::dot_wide(x, y))
+            .unwrap_or_else(|| kernel::squared_euclidean_wide(x, y))
+            .sqrt()
+    }
+}
+
+/// The serialised form of a [`NormCachedEuclidean`], before it is checked.
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+#[serde(rename = "NormCachedEuclidean")]
+struct Stored<T> {
+    point: T,
+    squared_norm: f64,
+}
+
+/// The stored norm is not trusted: the point is validated as in `try_new`, and its norm
+/// recomputed and compared against the stored one. Trusted inputs can keep the stored norm
+/// without recomputing it through [`unchecked::deserialize`](crate::unchecked::deserialize).
+#[cfg(feature = "serde")]
+impl<'de, T> Deserialize<'de> for NormCachedEuclidean<T>
+where
+    T: Deserialize<'de> + Coordinates,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let Stored {
+            point,
+            squared_norm,
+        } = Stored::<T>::deserialize(deserializer)?;
+        let point = Self::try_new(point).map_err(D::Error::custom)?;
+        // The summation order may differ between builds, so allow for rounding.
+        let dimension = point.point.coordinates().len() as f64;
+        let tolerance = point.squared_norm * dimension * f64::EPSILON;
+        if squared_norm.is_nan() || (squared_norm - point.squared_norm).abs() > tolerance {
+            return Err(D::Error::custom(format!(
+                "squared norm {squared_norm} does not match the point"
+            )));
+        }
+        Ok(point)
+    }
+}
+
+/// Keeps the stored norm as is, which must also be the squared norm of the point.
+#[cfg(feature = "serde")]
+impl<'de, T> crate::unchecked::Unchecked<'de> for NormCachedEuclidean<T>
+where
+    T: Deserialize<'de>,
+{
+    unsafe fn deserialize_unchecked<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let Stored {
+            point,
+            squared_norm,
+        } = Stored::<T>::deserialize(deserializer)?;
+        Ok(Self {
+            point,
+            squared_norm,
+        })
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roundtrip() {
+        let point = NormCachedEuclidean::try_new(vec![3.0, 4.0]).unwrap();
+        let json = serde_json::to_string(&point).unwrap();
+        let decoded: NormCachedEuclidean<Vec<f64>> = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.squared_norm(), 25.0);
+
+        let bytes = bincode::serialize(&point).unwrap();
+        let decoded: NormCachedEuclidean<Vec<f64>> = bincode::deserialize(&bytes).unwrap();
+        assert_eq!(*decoded, [3.0, 4.0]);
+    }
+
+    #[test]
+    fn unchecked_keeps_stored_norm() {
+        let json = r#"{"point": [3.0, 4.0], "squared_norm": 25.5}"#;
+        let mut deserializer = serde_json::Deserializer::from_str(json);
+        let point: NormCachedEuclidean<Vec<f64>> =
+            unsafe { crate::unchecked::deserialize(&mut deserializer) }.unwrap();
+        assert_eq!(point.squared_norm(), 25.5);
+        assert_eq!(*point, [3.0, 4.0]);
+    }
+
+    #[test]
+    fn rejects_wrong_norm() {
+        for json in [
+            r#"{"point": [3.0, 4.0], "squared_norm": 24.0}"#,
+            r#"{"point": [3.0, 4.0], "squared_norm": -25.0}"#,
+            r#"{"point": [3.0, 4.0], "squared_norm": 1e300}"#,
+        ] {
+            let error = serde_json::from_str::<NormCachedEuclidean<Vec<f64>>>(json).unwrap_err();
+            assert!(error.to_string().contains("does not match"), "{error}");
+        }
+
+        let mut point = NormCachedEuclidean::try_new(vec![3.0, 4.0]).unwrap();
+        point.squared_norm = f64::NAN;
+        let bytes = bincode::serialize(&point).unwrap();
+        assert!(bincode::deserialize::<NormCachedEuclidean<Vec<f64>>>(&bytes).is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_point() {
+        let json = r#"{"point": [0.0, 1e300], "squared_norm": 1e600}"#;
+        assert!(serde_json::from_str::<NormCachedEuclidean<Vec<f64>>>(json).is_err());
+
+        let json = r#"{"point": [0.0, 1e300], "squared_norm": 1e300}"#;
+        let error = serde_json::from_str::<NormCachedEuclidean<Vec<f64>>>(json).unwrap_err();
+        assert!(error.to_string().contains("out of range"), "{error}");
+    }
+}
@@ -0,0 +1,506 @@
+//! Import and export of NumPy `.npy` arrays.
+//!
+//! A 2-D array of `f32` or `f64` is read as a [`FastEuclideanDataset`] with one point per row. Both
+//! C and Fortran order are accepted, in either byte order, and every point is validated once the
+//! array is loaded. Row-major buffers, such as the output of [`pairwise_distances`](crate::pairwise_distances),
+//! can be written back out for analysis in NumPy.
+
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use crate::vecs::read_full;
+use crate::{Element, FastEuclideanDataset, FastEuclideanDatasetView, FileError};
+
+const MAGIC: &[u8; 6] = b"\x93NUMPY";
+/// Alignment of the start of the array data, including the preamble.
+const ALIGNMENT: usize = 64;
+/// Number of values decoded at a time.
+const BLOCK: usize = 4096;
+/// Longest header accepted, far beyond what a 2-D array needs, so that a corrupt length cannot
+/// force a large allocation either.
+const MAX_HEADER: usize = 1 << 16;
+
+/// Reads a 2-D `.npy` array from `reader`, one point per row.
+///
+/// The array must hold `f32` or `f64` values, which are converted to `E`.
+pub fn read<R, E>(mut reader: R) -> Result<FastEuclideanDataset<E>, FileError>
+where
+    R: Read,
+    E: Element,
+{
+    let header = Header::read(&mut reader)?;
+    let [rows, columns] = header.shape;
+    if columns == 0 {
+        return Err(FileError::Format("array has no columns".into()));
+    }
+    let len = rows
+        .checked_mul(columns)
+        .ok_or_else(|| FileError::Format("array does not fit in memory".into()))?;
+
+    let size = header.dtype.size();
+    // The buffer grows as values are read, so a corrupt shape cannot force a large allocation.
+    let mut data = Vec::new();
+    let mut bytes = vec![0; BLOCK * size];
+    while data.len() < len {
+        let bytes = &mut bytes[..(len - data.len()).min(BLOCK) * size];
+        let filled = read_full(&mut reader, bytes)?;
+        data.extend(
+            bytes[..filled]
+                .chunks_exact(size)
+                .map(|value| E::from_f64(header.dtype.decode(value))),
+        );
+        if filled < bytes.len() {
+            let read = data.len();
+            // Fortran order stores the array column by column, so every point is incomplete
+            // unless the file ends in the last column.
+            let point = if !header.fortran_order {
+                read / columns
+            } else if read / rows == columns - 1 {
+                read % rows
+            } else {
+                0
+            };
+            return Err(FileError::Truncated { point });
+        }
+    }
+    if header.fortran_order {
+        data = (0..len)
+            .map(|index| data[(index % columns) * rows + index / columns])
+            .collect();
+    }
+    Ok(FastEuclideanDataset::from_flat(columns, data)?)
+}
+
+/// Reads the 2-D `.npy` array in the file at `path`, as in [`read`].
+/// # Example
+/// ```no_run
+/// # use bitpart_fast_euclidean::npy;
+/// let dataset = npy::open::<_, f32>("embeddings.npy").unwrap();
+/// println!("{} points of dimension {}", dataset.len(), dataset.dimension());
+/// ```
+pub fn open<P, E>(path: P) -> Result<FastEuclideanDataset<E>, FileError>
+where
+    P: AsRef<Path>,
+    E: Element,
+{
+    read(BufReader::new(File::open(path)?))
+}
+
+/// Writes a row-major `rows` × `columns` buffer to `writer` as a little-endian `.npy` array.
+///
+/// Only `f32` and `f64` elements can be written; half-precision buffers are rejected.
+/// # Panics
+/// Panics if `data.len()` is not `rows * columns`.
+/// # Example
+/// ```
+/// # use bitpart_fast_euclidean::{npy, pairwise_distances, FastEuclidean};
+/// let points = [[0.0, 0.0], [3.0, 4.0]].map(|p| FastEuclidean::<[f64; 2]>::try_new(p).unwrap());
+/// let mut distances = [0.0; 4];
+/// pairwise_distances(&points, &points, &mut distances);
+///
+/// let mut file = Vec::new();
+/// npy::write(&mut file, 2, 2, &distances).unwrap();
+/// ```
+pub fn write<W, E>(writer: W, rows: usize, columns: usize, data: &[E]) -> Result<(), FileError>
+where
+    W: Write,
+    E: Element,
+{
+    assert_eq!(
+        data.len(),
+        rows * columns,
+        "buffer does not have rows * columns values"
+    );
+    let dtype = Dtype::of::<E>()
+        .ok_or_else(|| FileError::Format("only f32 and f64 arrays can be written".into()))?;
+
+    let mut writer = BufWriter::new(writer);
+    Header {
+        dtype,
+        fortran_order: false,
+        shape: [rows, columns],
+    }
+    .write(&mut writer)?;
+    for value in data {
+        dtype.encode(value.to_f64(), &mut writer)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Writes a dataset to `writer` as a `.npy` array with one point per row, as in [`write`](fn@write).
+pub fn write_dataset<W, E>(
+    writer: W,
+    dataset: FastEuclideanDatasetView<'_, E>,
+) -> Result<(), FileError>
+where
+    W: Write,
+    E: Element,
+{
+    write(
+        writer,
+        dataset.len(),
+        dataset.dimension(),
+        dataset.as_flat(),
+    )
+}
+
+/// Floating-point types that can be stored in an array.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Dtype {
+    F32 { big_endian: bool },
+    F64 { big_endian: bool },
+}
+
+impl Dtype {
+    fn parse(descr: &str) -> Result<Self, FileError> {
+        let big_endian = match descr.as_bytes().first() {
+            Some(b'<') => false,
+            Some(b'>') => true,
+            Some(b'=') => cfg!(target_endian = "big"),
+            _ => return Err(FileError::Format(format!("unsupported dtype {descr:?}"))),
+        };
+        match &descr[1..] {
+            "f4" => Ok(Dtype::F32 { big_endian }),
+            "f8" => Ok(Dtype::F64 { big_endian }),
+            _ => Err(FileError::Format(format!("unsupported dtype {descr:?}"))),
+        }
+    }
+
+    /// The little-endian dtype that stores `E`, if it is `f32` or `f64`.
+    fn of<E: Element>() -> Option<Self> {
+        match E::TYPE_CODE {
+            1 => Some(Dtype::F32 { big_endian: false }),
+            2 => Some(Dtype::F64 { big_endian: false }),
+            _ => None,
+        }
+    }
+
+    fn descr(self) -> &'static str {
+        match self {
+            Dtype::F32 { big_endian: false } => "<f4",
+            Dtype::F32 { big_endian: true } => ">f4",
+            Dtype::F64 { big_endian: false } => "<f8",
+            Dtype::F64 { big_endian: true } => ">f8",
+        }
+    }
+
+    fn size(self) -> usize {
+        match self {
+            Dtype::F32 { .. } => 4,
+            Dtype::F64 { .. } => 8,
+        }
+    }
+
+    fn decode(self, bytes: &[u8]) -> f64 {
+        match self {
+            Dtype::F32 { big_endian } => {
+                let bytes = bytes.try_into().unwrap();
+                if big_endian {
+                    f32::from_be_bytes(bytes) as f64
+                } else {
+                    f32::from_le_bytes(bytes) as f64
+                }
+            }
+            Dtype::F64 { big_endian } => {
+                let bytes = bytes.try_into().unwrap();
+                if big_endian {
+                    f64::from_be_bytes(bytes)
+                } else {
+                    f64::from_le_bytes(bytes)
+                }
+            }
+        }
+    }
+
+    fn encode<W: Write>(self, value: f64, writer: &mut W) -> Result<(), FileError> {
+        match self {
+            Dtype::F32 { .. } => writer.write_all(&(value as f32).to_le_bytes())?,
+            Dtype::F64 { .. } => writer.write_all(&value.to_le_bytes())?,
+        }
+        Ok(())
+    }
+}
+
+/// The header of a 2-D array.
+#[derive(Debug)]
+struct Header {
+    dtype: Dtype,
+    fortran_order: bool,
+    shape: [usize; 2],
+}
+
+impl Header {
+    fn read<R: Read>(reader: &mut R) -> Result<Self, FileError> {
+        let mut preamble = [0; 8];
+        reader.read_exact(&mut preamble)?;
+        if &preamble[..6] != MAGIC {
+            return Err(FileError::Format("missing magic bytes".into()));
+        }
+        let len = match preamble[6] {
+            1 => {
+                let mut len = [0; 2];
+                reader.read_exact(&mut len)?;
+                u16::from_le_bytes(len) as usize
+            }
+            2 | 3 => {
+                let mut len = [0; 4];
+                reader.read_exact(&mut len)?;
+                u32::from_le_bytes(len) as usize
+            }
+            major => return Err(FileError::Format(format!("unsupported version {major}"))),
+        };
+        if len > MAX_HEADER {
+            return Err(FileError::Format(format!(
+                "header is too long ({len} bytes)"
+            )));
+        }
+        let mut header = vec![0; len];
+        reader.read_exact(&mut header)?;
+        let header = String::from_utf8(header)
+            .map_err(|_| FileError::Format("header is not valid UTF-8".into()))?;
+        Self::parse(&header)
+    }
+
+    /// Parses the Python dictionary literal describing the array.
+    fn parse(header: &str) -> Result<Self, FileError> {
+        let mut parser = Parser(header.trim_end());
+        let (mut descr, mut fortran_order, mut shape) = (None, None, None);
+        parser.expect('{')?;
+        while !parser.eat('}') {
+            let key = parser.string()?;
+            parser.expect(':')?;
+            match key {
+                "descr" => descr = Some(Dtype::parse(parser.string()?)?),
+                "fortran_order" => fortran_order = Some(parser.bool()?),
+                "shape" => shape = Some(parser.tuple()?),
+                _ => return Err(FileError::Format(format!("unexpected header key {key:?}"))),
+            }
+            if !parser.eat(',') {
+                parser.expect('}')?;
+                break;
+            }
+        }
+        let missing = |key| FileError::Format(format!("header is missing {key:?}"));
+        let shape = shape.ok_or_else(|| missing("shape"))?;
+        let shape = <[usize; 2]>::try_from(shape).map_err(|shape| {
+            FileError::Format(format!("array has {} dimensions, expected 2", shape.len()))
+        })?;
+        Ok(Self {
+            dtype: descr.ok_or_else(|| missing("descr"))?,
+            fortran_order: fortran_order.ok_or_else(|| missing("fortran_order"))?,
+            shape,
+        })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<(), FileError> {
+        let mut header = format!(
+            "{{'descr': '{}', 'fortran_order': {}, 'shape': ({}, {}), }}",
+            self.dtype.descr(),
+            if self.fortran_order { "True" } else { "False" },
+            self.shape[0],
+            self.shape[1],
+        );
+        // Version 1 has a 10-byte preamble and version 2 a 12-byte one. Pad with spaces so that
+        // the header, terminated by a newline, ends on an alignment boundary.
+        let version = if header.len() + 11 <= u16::MAX as usize {
+            1
+        } else {
+            2
+        };
+        let preamble = if version == 1 { 10 } else { 12 };
+        let padding = (ALIGNMENT - (preamble + header.len() + 1) % ALIGNMENT) % ALIGNMENT;
+        header.extend(std::iter::repeat_n(' ', padding));
+        header.push('\n');
+
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[version, 0])?;
+        if version == 1 {
+            writer.write_all(&(header.len() as u16).to_le_bytes())?;
+        } else {
+            writer.write_all(&(header.len() as u32).to_le_bytes())?;
+        }
+        writer.write_all(header.as_bytes())?;
+        Ok(())
+    }
+}
+
+/// Parser for the subset of Python literal syntax used in headers.
+struct Parser<'a>(&'a str);
+
+impl<'a> Parser<'a> {
+    fn skip_whitespace(&mut self) {
+        self.0 = self.0.trim_start();
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        match self.0.strip_prefix(c) {
+            Some(rest) => {
+                self.0 = rest;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), FileError> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(FileError::Format(format!("expected {c:?} in header")))
+        }
+    }
+
+    fn string(&mut self) -> Result<&'a str, FileError> {
+        self.skip_whitespace();
+        let quote = match self.0.chars().next() {
+            Some(quote @ ('\'' | '"')) => quote,
+            _ => return Err(FileError::Format("expected string in header".into())),
+        };
+        let (string, rest) = self.0[1..]
+            .split_once(quote)
+            .ok_or_else(|| FileError::Format("unterminated string in header".into()))?;
+        self.0 = rest;
+        Ok(string)
+    }
+
+    fn bool(&mut self) -> Result<bool, FileError> {
+        self.skip_whitespace();
+        for (literal, value) in [("True", true), ("False", false)] {
+            if let Some(rest) = self.0.strip_prefix(literal) {
+                self.0 = rest;
+                return Ok(value);
+            }
+        }
+        Err(FileError::Format("expected boolean in header".into()))
+    }
+
+    fn tuple(&mut self) -> Result<Vec<usize>, FileError> {
+        self.expect('(')?;
+        let mut values = Vec::new();
+        while !self.eat(')') {
+            self.skip_whitespace();
+            let end = self
+                .0
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(self.0.len());
+            let value = self.0[..end]
+                .parse()
+                .map_err(|_| FileError::Format("expected integer in header".into()))?;
+            self.0 = &self.0[end..];
+            values.push(value);
+            if !self.eat(',') {
+                self.expect(')')?;
+                break;
+            }
+        }
+        Ok(values)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a version 1 `.npy` file from its header fields and raw data.
+    fn npy(descr: &str, fortran_order: bool, shape: &str, data: &[u8]) -> Vec<u8> {
+        let fortran_order = if fortran_order { "True" } else { "False" };
+        let header =
+            format!("{{'descr': '{descr}', 'fortran_order': {fortran_order}, 'shape': {shape}}}\n");
+        let mut file = MAGIC.to_vec();
+        file.extend([1, 0]);
+        file.extend((header.len() as u16).to_le_bytes());
+        file.extend(header.as_bytes());
+        file.extend(data);
+        file
+    }
+
+    /// Encodes `values` in the byte order and width of `descr`.
+    fn encode(descr: &str, values: &[f64]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for &value in values {
+            match descr {
+                "<f4" => bytes.extend((value as f32).to_le_bytes()),
+                ">f4" => bytes.extend((value as f32).to_be_bytes()),
+                "<f8" => bytes.extend(value.to_le_bytes()),
+                ">f8" => bytes.extend(value.to_be_bytes()),
+                _ => unreachable!(),
+            }
+        }
+        bytes
+    }
+
+    #[test]
+    fn reads_every_layout() {
+        // The 2 × 3 array [[0, 1, 2], [3, 4, 5]], stored by rows and by columns.
+        let rows = [0.0, 1.0, 2.0, 3.0, 4.0, 5.0];
+        let columns = [0.0, 3.0, 1.0, 4.0, 2.0, 5.0];
+        for descr in ["<f4", ">f4", "<f8", ">f8"] {
+            for (fortran_order, values) in [(false, rows), (true, columns)] {
+                let file = npy(descr, fortran_order, "(2, 3)", &encode(descr, &values));
+                let dataset = read::<_, f64>(&file[..]).unwrap();
+                assert_eq!(dataset.dimension(), 3, "{descr}, fortran {fortran_order}");
+                assert_eq!(dataset.as_flat(), rows, "{descr}, fortran {fortran_order}");
+            }
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let data = [0.5_f32, -1.0, 2.0, 3.25];
+        let mut file = Vec::new();
+        write(&mut file, 2, 2, &data).unwrap();
+        let dataset = read::<_, f32>(&file[..]).unwrap();
+        assert_eq!(dataset.as_flat(), data);
+    }
+
+    #[test]
+    fn reports_truncated_point() {
+        // Three of the six values of a 3 × 2 array.
+        let file = npy("<f8", false, "(3, 2)", &encode("<f8", &[0.0; 3]));
+        assert!(matches!(
+            read::<_, f64>(&file[..]),
+            Err(FileError::Truncated { point: 1 })
+        ));
+
+        // In Fortran order, the first column and one value of the second.
+        let file = npy("<f8", true, "(3, 2)", &encode("<f8", &[0.0; 4]));
+        assert!(matches!(
+            read::<_, f64>(&file[..]),
+            Err(FileError::Truncated { point: 1 })
+        ));
+        let file = npy("<f8", true, "(3, 2)", &encode("<f8", &[0.0; 2]));
+        assert!(matches!(
+            read::<_, f64>(&file[..]),
+            Err(FileError::Truncated { point: 0 })
+        ));
+    }
+
+    #[test]
+    fn rejects_bad_headers() {
+        let expect_format = |file: Vec<u8>, message: &str| match read::<_, f64>(&file[..]) {
+            Err(FileError::Format(m)) => assert!(m.contains(message), "{m}"),
+            other => panic!("expected {message:?}, got {other:?}"),
+        };
+        let data = encode("<f8", &[0.0; 6]);
+        expect_format(npy("<f8", false, "(6,)", &data), "1 dimensions");
+        expect_format(npy("<i4", false, "(2, 3)", &data), "unsupported dtype");
+        expect_format(npy("<f8", false, "(2, 0)", &data), "no columns");
+
+        let mut file = MAGIC.to_vec();
+        file.extend([2, 0]);
+        file.extend(u32::MAX.to_le_bytes());
+        expect_format(file, "too long");
+    }
+
+    #[test]
+    fn rejects_invalid_values() {
+        let file = npy("<f8", false, "(1, 2)", &encode("<f8", &[0.0, f64::NAN]));
+        assert!(matches!(
+            read::<_, f64>(&file[..]),
+            Err(FileError::Dataset(_))
+        ));
+    }
+}
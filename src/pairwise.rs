@@ -0,0 +1,113 @@
+//! Distances between every pair of points from two sets.
+
+use crate::kernel;
+use crate::{Coordinates, FastEuclidean};
+
+/// Number of points from each set processed together.
+const TILE: usize = 32;
+
+/// Number of coordinates of each point processed together.
+const DIMENSION_BLOCK: usize = 256;
+
+/// Squared distances below this fraction of `‖x‖² + ‖y‖²` are recomputed from the coordinates.
+///
+/// The error of the norm expansion grows with the norms rather than with the distance, so it is
+/// only trusted while the distance is a reasonable fraction of the norms.
+const CANCELLATION_THRESHOLD: f64 = 1e-6;
+
+/// Calculates the distance between every point in `rows` and every point in `columns`.
+///
+/// The distance between `rows[i]` and `columns[j]` is written to `out[i * columns.len() + j]`.
+///
+/// Squared distances are calculated as `‖x‖² + ‖y‖² − 2x·y`, with the dot products computed over
+/// tiles of both sets so that each tile stays in cache. Small negative results caused by rounding
+/// are clamped to zero, and pairs close enough for cancellation to dominate the result are
+/// recomputed directly from their differences. All arithmetic is in `f64`.
+/// # Panics
+/// Panics if `out.len() != rows.len() * columns.len()`.
+/// # Example
+/// ```
+/// # use bitpart_fast_euclidean::{pairwise_distances, FastEuclidean};
+/// let points: Vec<_> = [[0.0, 0.0], [3.0, 4.0]]
+///     .into_iter()
+///     .map(|p| FastEuclidean::<[f64; 2]>::try_new(p).unwrap())
+///     .collect();
+///
+/// let mut out = [0.0; 4];
+/// pairwise_distances(&points, &points, &mut out);
+/// assert_eq!(out, [0.0, 5.0, 5.0, 0.0]);
+/// ```
+pub fn pairwise_distances<T>(
+    rows: &[FastEuclidean<T>],
+    columns: &[FastEuclidean<T>],
+    out: &mut [f64],
+) where
+    T: Coordinates,
+{
+    assert_eq!(
+        out.len(),
+        rows.len() * columns.len(),
+        "output length does not match number of pairs"
+    );
+    let width = columns.len();
+    let row_norms: Vec<f64> = rows.iter().map(|x| squared_norm(x.coordinates())).collect();
+    let column_norms: Vec<f64> = columns
+        .iter()
+        .map(|y| squared_norm(y.coordinates()))
+        .collect();
+    let dimension = rows
+        .iter()
+        .chain(columns)
+        .map(|p| p.coordinates().len())
+        .max()
+        .unwrap_or(0);
+
+    out.fill(0.0);
+    for row_start in (0..rows.len()).step_by(TILE) {
+        let row_tile = &rows[row_start..rows.len().min(row_start + TILE)];
+        for column_start in (0..width).step_by(TILE) {
+            let column_tile = &columns[column_start..width.min(column_start + TILE)];
+            for block_start in (0..dimension).step_by(DIMENSION_BLOCK) {
+                let block = block_start..block_start + DIMENSION_BLOCK;
+                for (i, x) in row_tile.iter().enumerate() {
+                    let x = block_of(x.coordinates(), block.clone());
+                    let out =
+                        &mut out[(row_start + i) * width + column_start..][..column_tile.len()];
+                    for (out, y) in out.iter_mut().zip(column_tile) {
+                        *out += kernel::dot_wide(x, block_of(y.coordinates(), block.clone()));
+                    }
+                }
+            }
+        }
+    }
+
+    for ((out, x), x_norm) in out.chunks_mut(width.max(1)).zip(rows).zip(&row_norms) {
+        for ((out, y), y_norm) in out.iter_mut().zip(columns).zip(&column_norms) {
+            *out = match from_norms(*x_norm, *y_norm, *out) {
+                Some(squared) => squared,
+                None => kernel::squared_euclidean_wide(x.coordinates(), y.coordinates()),
+            }
+            .sqrt();
+        }
+    }
+}
+
+/// Combines squared norms and a dot product into a squared distance.
+///
+/// Returns `None` if cancellation could make the result inaccurate, in which case the squared
+/// distance should be computed from the coordinates instead.
+pub(crate) fn from_norms(x_norm: f64, y_norm: f64, dot: f64) -> Option<f64> {
+    let norms = x_norm + y_norm;
+    let squared = norms - 2.0 * dot;
+    (squared >= CANCELLATION_THRESHOLD * norms).then_some(squared.max(0.0))
+}
+
+/// Squared norm of `x`, accumulated in `f64`.
+pub(crate) fn squared_norm<E: crate::Element>(x: &[E]) -> f64 {
+    kernel::dot_wide(x, x)
+}
+
+/// Returns the coordinates of `x` within `block`, which may extend past the end of `x`.
+fn block_of<E>(x: &[E], block: std::ops::Range<usize>) -> &[E] {
+    &x[block.start.min(x.len())..block.end.min(x.len())]
+}
@@ -0,0 +1,21 @@
+//! Conversions between Euclidean and squared Euclidean thresholds.
+//!
+//! bitpart prunes its search using the triangle inequality, which squared Euclidean distance does
+//! not satisfy. Range queries must therefore always be given a Euclidean radius and run over
+//! [`FastEuclidean`](crate::FastEuclidean) points. Squared thresholds are only meant for comparing
+//! against [`squared_distance`](crate::FastEuclidean::squared_distance) outside of bitpart, for
+//! example when re-checking candidates or filtering results.
+
+/// Converts a Euclidean radius into the equivalent squared Euclidean threshold.
+///
+/// A distance `d` satisfies `d <= radius` if and only if `d * d <= to_squared(radius)`, for
+/// non-negative `radius`.
+pub fn to_squared(radius: f64) -> f64 {
+    radius * radius
+}
+
+/// Converts a squared Euclidean threshold back into the Euclidean radius expected by bitpart
+/// range queries.
+pub fn from_squared(threshold: f64) -> f64 {
+    threshold.sqrt()
+}
@@ -0,0 +1,54 @@
+//! Deserialisation of points from trusted inputs, skipping validation.
+//!
+//! The [`Deserialize`](serde::Deserialize) impls of [`FastEuclidean`](crate::FastEuclidean),
+//! [`FastEuclideanIter`](crate::FastEuclideanIter), [`FastManhattan`](crate::FastManhattan),
+//! [`FastChebyshev`](crate::FastChebyshev) and [`FastMinkowski`](crate::FastMinkowski) check every
+//! coordinate, and that of [`NormCachedEuclidean`](crate::NormCachedEuclidean) also recomputes
+//! the cached norm. For large inputs that are already known to be valid, such as files written by
+//! the same program, [`deserialize`] can be used instead through `#[serde(deserialize_with)]`. As
+//! it is `unsafe`, it must be called from a wrapper that states why the input is trusted.
+//! # Example
+//! ```
+//! # use bitpart_fast_euclidean::{unchecked, FastEuclidean};
+//! use serde::{Deserialize, Deserializer};
+//!
+//! #[derive(Deserialize)]
+//! struct Pivot {
+//!     name: String,
+//!     #[serde(deserialize_with = "trusted")]
+//!     point: FastEuclidean<[f64; 3]>,
+//! }
+//!
+//! fn trusted<'de, D>(deserializer: D) -> Result<FastEuclidean<[f64; 3]>, D::Error>
+//! where
+//!     D: Deserializer<'de>,
+//! {
+//!     // SAFETY: pivots are only ever written by the indexer, which validates them.
+//!     unsafe { unchecked::deserialize(deserializer) }
+//! }
+//! ```
+
+use serde::Deserializer;
+
+/// Fast metric wrappers that can be deserialised without validation.
+pub trait Unchecked<'de>: Sized {
+    /// Deserialises the wrapper in the same format as its validating `Deserialize` impl, but
+    /// without checking it.
+    /// # Safety
+    /// The deserialised value must satisfy the requirements of the wrapper's `new` constructor.
+    unsafe fn deserialize_unchecked<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>;
+}
+
+/// Deserialises a point into the fast metric wrapper `W` without validating it.
+/// # Safety
+/// The deserialised point must satisfy the requirements of the wrapper's `new` constructor, such
+/// as [`FastEuclidean::new`](crate::FastEuclidean::new).
+pub unsafe fn deserialize<'de, D, W>(deserializer: D) -> Result<W, D::Error>
+where
+    D: Deserializer<'de>,
+    W: Unchecked<'de>,
+{
+    W::deserialize_unchecked(deserializer)
+}
@@ -0,0 +1,373 @@
+//! Readers and writers for the `.fvecs`, `.ivecs` and `.bvecs` formats.
+//!
+//! These formats are used by the SIFT and GIST benchmark corpora. Each point is stored as its
+//! dimension, a little-endian `i32`, followed by its components: little-endian `f32`s for
+//! `.fvecs`, little-endian `i32`s for `.ivecs`, and bytes for `.bvecs`. Components are converted
+//! to the element type of the points, so `.fvecs` files can be read as either `f32` or `f64`
+//! points.
+//!
+//! Points are read one at a time, so files do not need to fit in memory.
+
+use std::fs::File;
+use std::io::{self, BufReader, ErrorKind, Read, Write};
+use std::marker::PhantomData;
+use std::path::Path;
+
+use crate::{validate, Coordinates, DatasetError, Element, FastEuclidean, FileError};
+
+/// Largest number of bytes of a point read at a time.
+const CHUNK: usize = 1 << 16;
+
+/// Component type of a vecs file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Format {
+    /// `.fvecs`: `f32` components.
+    Fvecs,
+    /// `.ivecs`: `i32` components.
+    Ivecs,
+    /// `.bvecs`: `u8` components.
+    Bvecs,
+}
+
+impl Format {
+    /// Guesses the format from the extension of `path`.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<Self> {
+        match path.as_ref().extension()?.to_str()? {
+            "fvecs" => Some(Format::Fvecs),
+            "ivecs" => Some(Format::Ivecs),
+            "bvecs" => Some(Format::Bvecs),
+            _ => None,
+        }
+    }
+
+    fn component_size(self) -> usize {
+        match self {
+            Format::Fvecs | Format::Ivecs => 4,
+            Format::Bvecs => 1,
+        }
+    }
+
+    fn decode(self, bytes: &[u8]) -> f64 {
+        match self {
+            Format::Fvecs => f32::from_le_bytes(bytes.try_into().unwrap()) as f64,
+            Format::Ivecs => i32::from_le_bytes(bytes.try_into().unwrap()) as f64,
+            Format::Bvecs => bytes[0] as f64,
+        }
+    }
+}
+
+/// Streaming reader yielding the points of a vecs file.
+///
+/// Every point is validated as in [`FastEuclidean::try_new`], and must have the same dimension as
+/// the first. Iteration stops after the first error.
+/// # Example
+/// ```no_run
+/// # use bitpart_fast_euclidean::vecs::Reader;
+/// let reader = Reader::<_, f32>::open("sift_base.fvecs").unwrap();
+/// for point in reader {
+///     let point = point.unwrap();
+///     // ...
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Reader<R, E = f64> {
+    reader: R,
+    format: Format,
+    dimension: Option<usize>,
+    point: usize,
+    buffer: Vec<u8>,
+    failed: bool,
+    _element: PhantomData<E>,
+}
+
+impl<E> Reader<BufReader<File>, E>
+where
+    E: Element,
+{
+    /// Opens the file at `path`, guessing its format from the extension.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, FileError> {
+        let format = Format::from_path(&path)
+            .ok_or_else(|| FileError::Format("extension is not .fvecs, .ivecs or .bvecs".into()))?;
+        Ok(Self::new(BufReader::new(File::open(path)?), format))
+    }
+}
+
+impl<R, E> Reader<R, E>
+where
+    R: Read,
+    E: Element,
+{
+    /// Creates a reader of points in `format` from `reader`.
+    pub fn new(reader: R, format: Format) -> Self {
+        Self {
+            reader,
+            format,
+            dimension: None,
+            point: 0,
+            buffer: Vec::new(),
+            failed: false,
+            _element: PhantomData,
+        }
+    }
+
+    /// Returns the dimension of the points read so far, or `None` if no point has been read.
+    pub fn dimension(&self) -> Option<usize> {
+        self.dimension
+    }
+
+    fn read_point(&mut self) -> Result<Option<FastEuclidean<Vec<E>>>, FileError> {
+        let mut header = [0; 4];
+        match read_full(&mut self.reader, &mut header)? {
+            0 => return Ok(None),
+            4 => {}
+            _ => return Err(FileError::Truncated { point: self.point }),
+        }
+        let found = i32::from_le_bytes(header);
+        let found = usize::try_from(found).map_err(|_| {
+            FileError::Format(format!(
+                "point {} has negative dimension {found}",
+                self.point
+            ))
+        })?;
+        let expected = *self.dimension.get_or_insert(found);
+        if found != expected {
+            return Err(DatasetError::Dimension {
+                point: self.point,
+                expected,
+                found,
+            }
+            .into());
+        }
+
+        // The buffer grows a chunk at a time, so a corrupt dimension cannot force a large
+        // allocation before the data runs out.
+        let len = found * self.format.component_size();
+        self.buffer.clear();
+        while self.buffer.len() < len {
+            let start = self.buffer.len();
+            self.buffer.resize(len.min(start + CHUNK), 0);
+            if read_full(&mut self.reader, &mut self.buffer[start..])? != self.buffer.len() - start
+            {
+                return Err(FileError::Truncated { point: self.point });
+            }
+        }
+        let coordinates: Vec<E> = self
+            .buffer
+            .chunks_exact(self.format.component_size())
+            .map(|bytes| E::from_f64(self.format.decode(bytes)))
+            .collect();
+        validate(&coordinates).map_err(|source| DatasetError::Invalid {
+            point: self.point,
+            source,
+        })?;
+        self.point += 1;
+        Ok(Some(FastEuclidean(coordinates)))
+    }
+}
+
+impl<R, E> Iterator for Reader<R, E>
+where
+    R: Read,
+    E: Element,
+{
+    type Item = Result<FastEuclidean<Vec<E>>, FileError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let result = self.read_point().transpose();
+        self.failed = matches!(result, Some(Err(_)));
+        result
+    }
+}
+
+/// Reads into `buffer` until it is full or the reader is exhausted, returning the number of bytes
+/// read.
+pub(crate) fn read_full<R: Read>(reader: &mut R, buffer: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        match reader.read(&mut buffer[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+/// Writes `points` to `writer` in `format`, returning the number of points written.
+///
+/// Coordinates are narrowed to `f32` for `.fvecs`, and must be within its range. For `.ivecs` and
+/// `.bvecs` every coordinate must be an integer within range of the component type.
+/// # Example
+/// ```
+/// # use bitpart_fast_euclidean::{vecs, FastEuclidean};
+/// let points = [[0.0, 1.0], [2.0, 3.0]].map(|p| FastEuclidean::<[f64; 2]>::try_new(p).unwrap());
+/// let mut file = Vec::new();
+/// vecs::write(&mut file, vecs::Format::Bvecs, points).unwrap();
+/// assert_eq!(file, [2, 0, 0, 0, 0, 1, 2, 0, 0, 0, 2, 3]);
+/// ```
+pub fn write<W, I, T>(mut writer: W, format: Format, points: I) -> Result<usize, FileError>
+where
+    W: Write,
+    I: IntoIterator<Item = FastEuclidean<T>>,
+    T: Coordinates,
+{
+    let mut count = 0;
+    let mut buffer = Vec::new();
+    for point in points {
+        let coordinates = point.coordinates();
+        let dimension = i32::try_from(coordinates.len())
+            .map_err(|_| FileError::Format(format!("point {count} has too many coordinates")))?;
+        buffer.clear();
+        buffer.extend_from_slice(&dimension.to_le_bytes());
+        for (index, value) in coordinates.iter().map(|c| c.to_f64()).enumerate() {
+            let out_of_range = || {
+                FileError::Format(format!(
+                    "coordinate {index} of point {count} ({value}) cannot be stored in {format:?}"
+                ))
+            };
+            match format {
+                Format::Fvecs => {
+                    let narrowed = value as f32;
+                    if !narrowed.is_finite() {
+                        return Err(out_of_range());
+                    }
+                    buffer.extend_from_slice(&narrowed.to_le_bytes());
+                }
+                Format::Ivecs => {
+                    if value.fract() != 0.0 || value < i32::MIN as f64 || value > i32::MAX as f64 {
+                        return Err(out_of_range());
+                    }
+                    buffer.extend_from_slice(&(value as i32).to_le_bytes());
+                }
+                Format::Bvecs => {
+                    if value.fract() != 0.0 || !(0.0..=255.0).contains(&value) {
+                        return Err(out_of_range());
+                    }
+                    buffer.push(value as u8);
+                }
+            }
+        }
+        writer.write_all(&buffer)?;
+        count += 1;
+    }
+    writer.flush()?;
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Encodes points as a vecs file, with `component` encoding each coordinate.
+    fn vecs<C: Copy>(points: &[&[C]], component: impl Fn(C) -> Vec<u8>) -> Vec<u8> {
+        let mut file = Vec::new();
+        for point in points {
+            file.extend((point.len() as i32).to_le_bytes());
+            file.extend(point.iter().flat_map(|&c| component(c)));
+        }
+        file
+    }
+
+    fn read_all<E: Element>(file: &[u8], format: Format) -> Result<Vec<Vec<E>>, FileError> {
+        Reader::<_, E>::new(file, format)
+            .map(|point| point.map(|point| point.0))
+            .collect()
+    }
+
+    #[test]
+    fn decodes_every_format() {
+        let fvecs = vecs(&[&[0.5_f32, -1.0], &[2.0, 3.0]], |c| {
+            c.to_le_bytes().to_vec()
+        });
+        assert_eq!(
+            read_all::<f64>(&fvecs, Format::Fvecs).unwrap(),
+            [[0.5, -1.0], [2.0, 3.0]]
+        );
+        let ivecs = vecs(&[&[-7_i32, 1 << 20]], |c| c.to_le_bytes().to_vec());
+        assert_eq!(
+            read_all::<f64>(&ivecs, Format::Ivecs).unwrap(),
+            [[-7.0, (1 << 20) as f64]]
+        );
+        let bvecs = vecs(&[&[0_u8, 255, 3]], |c| vec![c]);
+        assert_eq!(
+            read_all::<f32>(&bvecs, Format::Bvecs).unwrap(),
+            [[0.0, 255.0, 3.0]]
+        );
+        assert!(read_all::<f32>(&[], Format::Bvecs).unwrap().is_empty());
+    }
+
+    #[test]
+    fn round_trip() {
+        let points =
+            [[1.0, 2.0], [3.0, 4.0]].map(|p| FastEuclidean::<[f64; 2]>::try_new(p).unwrap());
+        for format in [Format::Fvecs, Format::Ivecs, Format::Bvecs] {
+            let mut file = Vec::new();
+            assert_eq!(write(&mut file, format, points.clone()).unwrap(), 2);
+            assert_eq!(
+                read_all::<f64>(&file, format).unwrap(),
+                [[1.0, 2.0], [3.0, 4.0]]
+            );
+        }
+    }
+
+    #[test]
+    fn reports_truncation() {
+        let file = vecs(&[&[1_u8, 2], &[3, 4]], |c| vec![c]);
+        // Partway through the dimension of the second point.
+        assert!(matches!(
+            read_all::<f64>(&file[..8], Format::Bvecs),
+            Err(FileError::Truncated { point: 1 })
+        ));
+        // Partway through the coordinates of the second point.
+        assert!(matches!(
+            read_all::<f64>(&file[..11], Format::Bvecs),
+            Err(FileError::Truncated { point: 1 })
+        ));
+        // A corrupt dimension runs out of data rather than allocating it up front.
+        assert!(matches!(
+            read_all::<f64>(&i32::MAX.to_le_bytes(), Format::Fvecs),
+            Err(FileError::Truncated { point: 0 })
+        ));
+    }
+
+    #[test]
+    fn rejects_bad_dimensions() {
+        let file = vecs(&[&[1_u8, 2], &[3, 4, 5]], |c| vec![c]);
+        assert!(matches!(
+            read_all::<f64>(&file, Format::Bvecs),
+            Err(FileError::Dataset(DatasetError::Dimension {
+                point: 1,
+                expected: 2,
+                found: 3
+            }))
+        ));
+
+        let mut file = vecs(&[&[1_u8]], |c| vec![c]);
+        file.extend((-1_i32).to_le_bytes());
+        match read_all::<f64>(&file, Format::Bvecs) {
+            Err(FileError::Format(message)) => {
+                assert_eq!(message, "point 1 has negative dimension -1")
+            }
+            other => panic!("unexpected result {other:?}"),
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_points() {
+        let file = vecs(&[&[0.0_f32], &[f32::NAN]], |c| c.to_le_bytes().to_vec());
+        assert!(matches!(
+            read_all::<f64>(&file, Format::Fvecs),
+            Err(FileError::Dataset(DatasetError::Invalid { point: 1, .. }))
+        ));
+
+        // The iterator stops after the first error.
+        let mut reader = Reader::<_, f64>::new(&file[..], Format::Fvecs);
+        assert!(reader.next().unwrap().is_ok());
+        assert!(reader.next().unwrap().is_err());
+        assert!(reader.next().is_none());
+    }
+}
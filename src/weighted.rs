@@ -0,0 +1,289 @@
+use bitpart::metric::Metric;
+use std::ops::Deref;
+use std::sync::Arc;
+
+#[cfg(feature = "serde")]
+use serde::{de::DeserializeSeed, de::Error, Deserialize, Deserializer, Serialize, Serializer};
+#[cfg(feature = "serde")]
+use std::marker::PhantomData;
+
+use crate::{
+    check_dimensions, kernel, limit_for_sum, validate_within, Coordinates, PointMismatch,
+    ValidationError,
+};
+
+/// Per-coordinate weights shared by every point of a [`FastWeightedEuclidean`] dataset.
+///
+/// The weights are reference counted, so cloning them, or wrapping a point with them, does not
+/// copy the underlying slice. Every weight must be finite and non-negative.
+/// # Example
+/// ```
+/// # use bitpart_fast_euclidean::{ValidationError, Weights};
+/// assert!(Weights::try_new(vec![1.0, 0.5, 0.0]).is_ok());
+/// assert!(matches!(
+///     Weights::try_new(vec![1.0, -0.5]),
+///     Err(ValidationError::InvalidWeight { index: 1, .. })
+/// ));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Weights {
+    values: Arc<[f64]>,
+    max: f64,
+}
+
+impl Weights {
+    /// Creates a new set of weights, checking that each is finite and non-negative.
+    pub fn try_new(values: impl Into<Arc<[f64]>>) -> Result<Self, ValidationError> {
+        let values = values.into();
+        let mut max = 0.0_f64;
+        for (index, &value) in values.iter().enumerate() {
+            if !(value.is_finite() && value >= 0.0) {
+                return Err(ValidationError::InvalidWeight { index, value });
+            }
+            max = max.max(value);
+        }
+        Ok(Self { values, max })
+    }
+
+    /// Returns the number of weights, which is the dimension of every point using them.
+    pub fn dimension(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Returns a seed deserialising a point to be wrapped with these weights.
+    ///
+    /// Serialised [`FastWeightedEuclidean`] points only contain their coordinates, so the weights
+    /// must be supplied when deserialising them, typically after deserialising the weights once.
+    /// # Example
+    /// ```
+    /// # use bitpart_fast_euclidean::{FastWeightedEuclidean, Weights};
+    /// use serde::de::{value::SeqDeserializer, DeserializeSeed};
+    ///
+    /// let weights = Weights::try_new(vec![1.0, 2.0]).unwrap();
+    /// let coordinates = SeqDeserializer::<_, serde::de::value::Error>::new([3.0, 4.0].into_iter());
+    /// let point: FastWeightedEuclidean<Vec<f64>> = weights.seed().deserialize(coordinates).unwrap();
+    ///
+    /// assert_eq!(*point, [3.0, 4.0]);
+    /// ```
+    #[cfg(feature = "serde")]
+    pub fn seed<T>(&self) -> WeightedSeed<T> {
+        WeightedSeed {
+            weights: self.clone(),
+            marker: PhantomData,
+        }
+    }
+
+    /// Largest coordinate magnitude accepted for a point using these weights.
+    ///
+    /// Weights below 1 are treated as 1, as each squared difference is computed before it is
+    /// weighted.
+    fn coordinate_limit(&self) -> f64 {
+        limit_for_sum(f64::MAX, self.values.len(), 2.0, self.max.max(1.0))
+    }
+}
+
+impl Deref for Weights {
+    type Target = [f64];
+
+    fn deref(&self) -> &Self::Target {
+        &self.values
+    }
+}
+
+impl PartialEq for Weights {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.values, &other.values) || self.values == other.values
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for Weights {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.values.serialize(serializer)
+    }
+}
+
+/// Weights are validated as in `try_new`.
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for Weights {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Self::try_new(Vec::<f64>::deserialize(deserializer)?).map_err(D::Error::custom)
+    }
+}
+
+/// Wrapper struct to apply weighted Euclidean distance to an object set.
+///
+/// The distance is `√(Σwᵢ(xᵢ - yᵢ)²)`, where the weights are shared by every point through
+/// [`Weights`], and accumulated in `f64` whatever the element type. This avoids rescaling the
+/// points themselves, which keep their original coordinates. With the `serde` feature, only the
+/// coordinates are serialised; see [`Weights::seed`] to deserialise them.
+/// # Example
+/// ```
+/// # use bitpart::metric::Metric;
+/// # use bitpart_fast_euclidean::{FastWeightedEuclidean, Weights};
+/// let weights = Weights::try_new(vec![4.0, 1.0]).unwrap();
+/// let point1 = FastWeightedEuclidean::try_new([0.0, 0.0], &weights).unwrap();
+/// let point2 = FastWeightedEuclidean::try_new([1.5, 4.0], &weights).unwrap();
+///
+/// assert_eq!(point1.distance(&point2), 5.0);
+/// ```
+#[derive(Debug, Clone)]
+pub struct FastWeightedEuclidean<T> {
+    point: T,
+    weights: Weights,
+}
+
+impl<T> FastWeightedEuclidean<T> {
+    /// Creates a new `FastWeightedEuclidean`.
+    /// # Safety
+    /// You **must** ensure that the point has the same dimension as `weights`, and that the
+    /// weighted euclidean distance measurements between any two points cannot return
+    /// [`NaN`](f64::NAN) nor [`INF`](f64::INFINITY).
+    pub unsafe fn new(t: T, weights: &Weights) -> Self {
+        Self {
+            point: t,
+            weights: weights.clone(),
+        }
+    }
+
+    /// Returns the weights shared with the other points.
+    pub fn weights(&self) -> &Weights {
+        &self.weights
+    }
+
+    /// Returns the wrapped point, discarding the weights.
+    pub fn into_inner(self) -> T {
+        self.point
+    }
+}
+
+impl<T> FastWeightedEuclidean<T>
+where
+    T: Coordinates,
+{
+    /// Creates a new `FastWeightedEuclidean`, checking that the safety requirements of
+    /// [`new`](Self::new) are upheld.
+    ///
+    /// Every coordinate must be finite, and small enough in magnitude that the weighted squared
+    /// distance to any other point passing this check cannot overflow.
+    pub fn try_new(t: T, weights: &Weights) -> Result<Self, ValidationError> {
+        let coordinates = t.coordinates();
+        if coordinates.len() != weights.dimension() {
+            return Err(ValidationError::Dimension {
+                expected: weights.dimension(),
+                found: coordinates.len(),
+            });
+        }
+        validate_within(coordinates, weights.coordinate_limit())?;
+        Ok(unsafe { Self::new(t, weights) })
+    }
+
+    /// Calculates the distance to `rhs`, first checking that both points have the same dimension
+    /// and the same weights.
+    pub fn try_distance(&self, rhs: &Self) -> Result<f64, PointMismatch>
+    where
+        T: Clone,
+    {
+        check_dimensions(self.point.coordinates(), rhs.point.coordinates())?;
+        if self.weights != rhs.weights {
+            return Err(PointMismatch::Weights);
+        }
+        Ok(self.distance(rhs))
+    }
+}
+
+impl<T> Deref for FastWeightedEuclidean<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.point
+    }
+}
+
+/// Bounds as for [`FastEuclidean`](crate::FastEuclidean).
+///
+/// Each point is only validated against its own weights, so comparing points with different
+/// weights could overflow. `distance` therefore panics if the weights differ, which is checked by
+/// pointer first, and only compares the values for weights that were created separately.
+impl<T> Metric for FastWeightedEuclidean<T>
+where
+    T: Coordinates + Clone,
+{
+    fn distance(&self, rhs: &FastWeightedEuclidean<T>) -> f64 {
+        let (x, y) = (self.point.coordinates(), rhs.point.coordinates());
+        debug_assert_eq!(x.len(), y.len(), "points have different dimensions");
+        assert!(self.weights == rhs.weights, "points have different weights");
+        kernel::weighted_squared_euclidean_wide(x, y, &self.weights).sqrt()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T> Serialize for FastWeightedEuclidean<T>
+where
+    T: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.point.serialize(serializer)
+    }
+}
+
+/// Deserialises a [`FastWeightedEuclidean`] point with the given weights, validating it as in
+/// `try_new`. Created by [`Weights::seed`].
+#[cfg(feature = "serde")]
+#[derive(Debug, Clone)]
+pub struct WeightedSeed<T> {
+    weights: Weights,
+    marker: PhantomData<fn() -> T>,
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T> DeserializeSeed<'de> for WeightedSeed<T>
+where
+    T: Deserialize<'de> + Coordinates,
+{
+    type Value = FastWeightedEuclidean<T>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        FastWeightedEuclidean::try_new(T::deserialize(deserializer)?, &self.weights)
+            .map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_different_weights() {
+        let small = Weights::try_new(vec![1.0]).unwrap();
+        let large = Weights::try_new(vec![1e300]).unwrap();
+        let point1 = FastWeightedEuclidean::try_new(vec![1e150], &small).unwrap();
+        let point2 = FastWeightedEuclidean::try_new(vec![0.0], &large).unwrap();
+        assert_eq!(point1.try_distance(&point2), Err(PointMismatch::Weights));
+
+        // Weights created separately with the same values are interchangeable.
+        let copy = Weights::try_new(vec![1.0]).unwrap();
+        let point3 = FastWeightedEuclidean::try_new(vec![0.0], &copy).unwrap();
+        assert_eq!(point1.try_distance(&point3), Ok(1e150));
+    }
+
+    #[test]
+    #[should_panic(expected = "points have different weights")]
+    fn distance_panics_on_different_weights() {
+        let point1 = FastWeightedEuclidean::try_new([1.0], &Weights::try_new([1.0]).unwrap());
+        let point2 = FastWeightedEuclidean::try_new([0.0], &Weights::try_new([2.0]).unwrap());
+        point1.unwrap().distance(&point2.unwrap());
+    }
+}